@@ -21,3 +21,161 @@ pub enum FillStrategies {
     Median,
     Mean,
 }
+
+/// Source channel the tracer reads pixel levels from before thresholding.
+#[derive(PartialEq, Clone)]
+pub enum Channels {
+    Red,
+    Green,
+    Blue,
+    /// Alpha channel, inverted so that opaque pixels read as dark
+    Alpha,
+    /// ITU-R BT.601 luma of the gamma-encoded values
+    Rec601,
+    /// ITU-R BT.709 luma of the gamma-encoded values
+    Rec709,
+    /// Relative luminance (Y) computed on linearized sRGB values
+    LinearLuminance,
+    /// CIE L* perceptual lightness
+    Lightness,
+    /// HSV value, the largest of the three components
+    Value,
+    /// HSV saturation
+    Saturation,
+    /// Weighted mix of the red, green and blue components. Weights are normalized.
+    Custom {
+        red: f64,
+        green: f64,
+        blue: f64,
+    },
+}
+
+/// How the distance between two colors is measured when matching pixels against a color.
+#[derive(PartialEq, Clone)]
+pub enum ColorMetrics {
+    /// Euclidean distance in RGB space, in range of 0..441
+    Rgb,
+    /// Angular distance between HSV hues in degrees. Achromatic pixels never match a
+    /// chromatic color.
+    Hue,
+    /// CIEDE2000 color difference between CIELAB colors
+    DeltaE2000,
+}
+
+/// Which neighbours of a pixel are considered connected to it.
+#[derive(PartialEq, Clone)]
+pub enum Connectivity {
+    /// pixels sharing an edge
+    Four,
+    /// pixels sharing an edge or a corner
+    Eight,
+}
+
+/// How the traced image is scaled into the requested width and height.
+#[derive(PartialEq, Clone)]
+pub enum FitModes {
+    /// scale each axis independently to fill the target exactly
+    Stretch,
+    /// scale uniformly so that the whole image fits inside the target
+    Contain,
+    /// scale uniformly so that the image covers the whole target, overflowing on one axis
+    Cover,
+}
+
+/// Where a uniformly scaled image is placed along an axis of the target, like the xMin, xMid
+/// and xMax values of SVG's preserveAspectRatio.
+#[derive(PartialEq, Clone)]
+pub enum Alignments {
+    Min,
+    Mid,
+    Max,
+}
+
+/// Units the dimensions of the SVG image, and the width, height and margin options, are in.
+#[derive(PartialEq, Clone)]
+pub enum Units {
+    /// CSS pixels, 96 per inch
+    Px,
+    /// points, 72 per inch
+    Pt,
+    Mm,
+    Cm,
+    In,
+}
+
+/// Size of the page the traced image is placed on.
+#[derive(PartialEq, Clone)]
+pub enum PageSizes {
+    /// 297 x 420 mm
+    A3,
+    /// 210 x 297 mm
+    A4,
+    /// 148 x 210 mm
+    A5,
+    /// 8.5 x 11 in
+    Letter,
+    /// 8.5 x 14 in
+    Legal,
+    /// width and height in the output units
+    Custom { width: f64, height: f64 },
+}
+
+/// Where polygons returned for traced shapes come from.
+#[derive(PartialEq, Clone)]
+pub enum PolygonModes {
+    /// the fitted curves, flattened to within a tolerance in output units
+    Flatten { tolerance: f64 },
+    /// the optimal polygon found before fitting curves, vertices lying on pixel corners
+    Optimal,
+}
+
+/// Algorithm used to reduce the number of vertices of traced polygons.
+#[derive(PartialEq, Clone)]
+pub enum SimplificationMethods {
+    /// Ramer-Douglas-Peucker, keeping removed vertices within the tolerance of the result
+    DouglasPeucker,
+    /// Visvalingam-Whyatt, removing vertices forming triangles smaller than tolerance squared
+    VisvalingamWhyatt,
+}
+
+/// How traced pixel boundaries are fitted with curves.
+#[derive(PartialEq, Clone)]
+pub enum CurveFitters {
+    /// potrace's optimal polygon, smoothed and optimized according to alphaMax and optCurve
+    Potrace,
+    /// least-squares fitting of cubic Bezier curves with corner detection, no boundary point
+    /// being farther than max_error pixels from the curve
+    LeastSquares { max_error: f64 },
+    /// the pixel boundary itself without any smoothing, collinear points merged into axis
+    /// aligned polygons, for pixel art and QR codes
+    PixelExact,
+}
+
+/// Which SVG path commands curves are written with.
+#[derive(PartialEq, Clone)]
+pub enum CurveCommands {
+    /// cubic Bezier curves (C), as traced
+    Cubic,
+    /// quadratic Bezier curves (Q), each within tolerance of the traced curve in output units
+    Quadratic { tolerance: f64 },
+    /// lines and circular arcs (A), fitted as biarcs within tolerance of the traced curve in
+    /// output units
+    Arcs { tolerance: f64 },
+}
+
+/// SVG fill-rule of the traced path.
+#[derive(PartialEq, Clone)]
+pub enum FillRules {
+    EvenOdd,
+    /// requires holes to run opposite to their outlines, see Windings
+    NonZero,
+}
+
+/// Direction traced curves run in, clockwise meaning clockwise on screen.
+#[derive(PartialEq, Clone)]
+pub enum Windings {
+    /// outlines clockwise and holes counterclockwise
+    OutlinesClockwise,
+    /// outlines counterclockwise and holes clockwise
+    OutlinesCounterclockwise,
+}
@@ -0,0 +1,645 @@
+use base64::decode;
+use std::collections::{HashMap, HashSet, VecDeque};
+use types::curve::fixed;
+use types::fit::fit_polyline;
+use types::point::Point;
+use types::segment::Segment;
+use types::transform::Transform;
+
+/// Offsets of the 8 neighbours of a pixel, clockwise starting north. The bit k of a pixel in
+/// the similarity graph is set when it is connected to its neighbour at NEIGHBOURS[k].
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// bits of the neighbours below and to the right of a pixel in the similarity graph
+const EAST: usize = 2;
+const SOUTH_EAST: usize = 3;
+const SOUTH: usize = 4;
+const SOUTH_WEST: usize = 5;
+
+/// weight the island heuristic gives to a diagonal keeping a pixel from being cut off
+const ISLAND_WEIGHT: usize = 5;
+
+/// side of the window the sparse pixels heuristic measures components in
+const SPARSE_WINDOW: usize = 8;
+
+/// number of units a pixel is divided into along each axis, for the reshaped cells to lie on
+/// integer coordinates
+const UNITS: i64 = 4;
+
+/**
+ * Depixelizer options
+ *
+ * @property {[f64; 4]} [threshold] - Largest differences in Y, U, V and alpha, on a 0..255 scale, for two neighbouring pixels to be part of the same feature. (default: [48, 7, 6, 48])
+ * @property {f64} [maxError] - Largest distance of the curves from the outlines of the reshaped pixels, in pixels. (default: 0.5)
+ * @property {f64} [scale] - Size of an input pixel in the SVG image. (default: 1)
+ */
+pub struct DepixelizerOptions {
+    pub threshold: [f64; 4],
+    pub maxError: f64,
+    pub scale: f64,
+}
+
+impl Default for DepixelizerOptions {
+    fn default() -> DepixelizerOptions {
+        DepixelizerOptions {
+            threshold: [48f64, 7f64, 6f64, 48f64],
+            maxError: 0.5,
+            scale: 1f64,
+        }
+    }
+}
+
+/// A region of similar pixels, outlined by smooth closed curves
+#[derive(Clone)]
+pub struct Region {
+    /// average color of the pixels, RGBA
+    pub color: [u8; 4],
+    /// number of pixels in the region
+    pub area: usize,
+    /// the outline and holes of the region as closed Bezier curves, in pixel coordinates.
+    /// Outlines run clockwise on screen and holes counterclockwise.
+    pub curves: Vec<Vec<Segment>>,
+}
+
+/// A boundary between two regions, or a region and the outside of the image, running between
+/// two junctions or around in a loop
+struct Chain {
+    points: Vec<(i64, i64)>,
+    closed: bool,
+    /// region on the right of the chain on screen, which it runs clockwise around
+    owner: usize,
+    /// region on the other side, None outside of the image
+    neighbour: Option<usize>,
+}
+
+/**
+ * Vectorizes pixel art the way Kopf and Lischinski's "Depixelizing Pixel-Art" does. Similar
+ * neighbouring pixels are connected in a graph, crossing diagonals are resolved with the curves,
+ * sparse pixels and islands heuristics, and every pixel cell is reshaped to follow the diagonals
+ * it keeps. The boundaries between connected regions are then fitted with Bezier curves, each
+ * one shared by the two regions on either side of it so that they meet without gaps.
+ */
+pub struct Depixelizer {
+    width: usize,
+    height: usize,
+    /// RGBA of every pixel, row by row
+    pixels: Vec<[u8; 4]>,
+    params: DepixelizerOptions,
+    regions: Option<Vec<Region>>,
+}
+
+impl Depixelizer {
+    pub fn new(base64: &str, options: Option<Box<DepixelizerOptions>>) -> Depixelizer {
+        let bytes = decode(base64).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let pixels = image.pixels().map(|pixel| pixel.0).collect();
+        return Depixelizer::from_pixels(pixels, width, height, options);
+    }
+
+    /// Creates a depixelizer for RGBA pixels given row by row
+    pub fn from_pixels(
+        pixels: Vec<[u8; 4]>,
+        width: usize,
+        height: usize,
+        options: Option<Box<DepixelizerOptions>>,
+    ) -> Depixelizer {
+        Depixelizer {
+            width,
+            height,
+            pixels,
+            params: match options {
+                Some(val) => *val,
+                None => DepixelizerOptions::default(),
+            },
+            regions: None,
+        }
+    }
+
+    pub fn set_parameters(&mut self, params: DepixelizerOptions) {
+        self.params = params;
+        self.regions = None;
+    }
+
+    /// Returns the regions of similar pixels with their curves, vectorizing the image on the
+    /// first call
+    pub fn get_regions(&mut self) -> Vec<Region> {
+        if self.regions.is_none() {
+            self.regions = Some(self.vectorize());
+        }
+        return self.regions.clone().unwrap();
+    }
+
+    /// Returns the image as SVG, one path per region. Fully transparent regions are left out.
+    pub fn get_svg(&mut self) -> String {
+        let scale = self.params.scale;
+        let (width, height) = (self.width as f64 * scale, self.height as f64 * scale);
+        let transform = Transform::scale(scale, scale);
+        let paths: Vec<String> = self
+            .get_regions()
+            .iter()
+            .filter(|region| region.color[3] != 0)
+            .map(|region| render_region(region, &transform))
+            .collect();
+        return format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" version=\"1.1\">{paths}</svg>",
+            w = fixed(width),
+            h = fixed(height),
+            paths = paths.join("")
+        );
+    }
+
+    fn vectorize(&self) -> Vec<Region> {
+        let graph = self.similarity_graph();
+        let (labels, count) = self.label_regions(&graph);
+        let mut regions: Vec<Region> = (0..count)
+            .map(|_| Region {
+                color: [0; 4],
+                area: 0,
+                curves: vec![],
+            })
+            .collect();
+        let mut sums = vec![[0usize; 4]; count];
+        for (idx, label) in labels.iter().enumerate() {
+            regions[*label].area += 1;
+            for channel in 0..4 {
+                sums[*label][channel] += self.pixels[idx][channel] as usize;
+            }
+        }
+        for (region, sum) in regions.iter_mut().zip(sums.iter()) {
+            for channel in 0..4 {
+                region.color[channel] = ((sum[channel] as f64 / region.area as f64).round()) as u8;
+            }
+        }
+
+        let chains = self.boundaries(&graph, &labels);
+        let fitted: Vec<Vec<Segment>> = chains
+            .iter()
+            .map(|chain| {
+                let points: Vec<Point> = chain
+                    .points
+                    .iter()
+                    .map(|(x, y)| Point::new(*x as f64 / UNITS as f64, *y as f64 / UNITS as f64))
+                    .collect();
+                fit_polyline(&points, chain.closed, self.params.maxError)
+            })
+            .collect();
+
+        // every region runs clockwise along the chains it owns and backwards along the chains
+        // it neighbours
+        let mut pieces: Vec<Vec<(usize, bool)>> = vec![vec![]; count];
+        for (index, chain) in chains.iter().enumerate() {
+            pieces[chain.owner].push((index, false));
+            if let Some(neighbour) = chain.neighbour {
+                pieces[neighbour].push((index, true));
+            }
+        }
+        for (label, region) in regions.iter_mut().enumerate() {
+            region.curves = assemble(&chains, &fitted, &pieces[label]);
+        }
+        return regions;
+    }
+
+    /// Returns whether two pixels are close enough in YUV and alpha to be connected
+    fn similar(&self, a: usize, b: usize) -> bool {
+        let (p, q) = (self.pixels[a], self.pixels[b]);
+        let (y1, u1, v1) = yuv(p);
+        let (y2, u2, v2) = yuv(q);
+        let threshold = self.params.threshold;
+        return (y1 - y2).abs() <= threshold[0]
+            && (u1 - u2).abs() <= threshold[1]
+            && (v1 - v2).abs() <= threshold[2]
+            && (p[3] as f64 - q[3] as f64).abs() <= threshold[3];
+    }
+
+    /// Returns the index of the pixel at the given offset from pixel idx, if it is in the image
+    fn offset(&self, idx: usize, offset: (isize, isize)) -> Option<usize> {
+        let x = (idx % self.width) as isize + offset.0;
+        let y = (idx / self.width) as isize + offset.1;
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        return Some(y as usize * self.width + x as usize);
+    }
+
+    /// Returns the pixels connected to pixel idx in the graph
+    fn neighbours(&self, graph: &[u8], idx: usize) -> Vec<usize> {
+        NEIGHBOURS
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| linked(graph, idx, *k))
+            .filter_map(|(_, offset)| self.offset(idx, *offset))
+            .collect()
+    }
+
+    /// Removes the connection from pixel idx to its neighbour k, and back
+    fn unlink(&self, graph: &mut [u8], idx: usize, k: usize) {
+        graph[idx] &= !(1 << k);
+        if let Some(other) = self.offset(idx, NEIGHBOURS[k]) {
+            graph[other] &= !(1 << ((k + 4) % 8));
+        }
+    }
+
+    /// Returns the number of connections in the curve through the connection from a to b,
+    /// following pixels with exactly two connections both ways
+    fn curve_length(&self, graph: &[u8], a: usize, b: usize) -> usize {
+        let mut length = 1;
+        for (start, from) in [(a, b), (b, a)].iter() {
+            let (mut previous, mut current) = (*from, *start);
+            while valence(graph, current) == 2 {
+                let next = self
+                    .neighbours(graph, current)
+                    .into_iter()
+                    .find(|idx| *idx != previous)
+                    .unwrap();
+                if next == a || next == b {
+                    // the curve is a closed loop, already counted in full
+                    return length + 1;
+                }
+                length += 1;
+                previous = current;
+                current = next;
+            }
+        }
+        return length;
+    }
+
+    /// Builds the graph connecting every pixel to its similar neighbours, then makes it planar
+    /// by removing one or both of every pair of crossing diagonals
+    fn similarity_graph(&self) -> Vec<u8> {
+        let mut graph = vec![0u8; self.pixels.len()];
+        for idx in 0..self.pixels.len() {
+            for (k, offset) in NEIGHBOURS.iter().enumerate() {
+                if let Some(other) = self.offset(idx, *offset) {
+                    if self.similar(idx, other) {
+                        graph[idx] |= 1 << k;
+                    }
+                }
+            }
+        }
+
+        let width = self.width;
+        for y in 0..self.height.saturating_sub(1) {
+            for x in 0..width.saturating_sub(1) {
+                // a b
+                // c d
+                let (a, b) = (y * width + x, y * width + x + 1);
+                let (c, d) = (a + width, b + width);
+                if !linked(&graph, a, SOUTH_EAST) || !linked(&graph, b, SOUTH_WEST) {
+                    continue;
+                }
+                let square = linked(&graph, a, EAST)
+                    && linked(&graph, a, SOUTH)
+                    && linked(&graph, b, SOUTH)
+                    && linked(&graph, c, EAST);
+                let (first, second) = if square {
+                    // a block of a single color, where the diagonals add nothing
+                    (0, 0)
+                } else {
+                    self.weigh_diagonals(&graph, (a, d), (b, c))
+                };
+                if first <= second {
+                    self.unlink(&mut graph, a, SOUTH_EAST);
+                }
+                if second <= first {
+                    self.unlink(&mut graph, b, SOUTH_WEST);
+                }
+            }
+        }
+        return graph;
+    }
+
+    /// Weighs two crossing diagonals with the curves, sparse pixels and islands heuristics. The
+    /// diagonal with the lower weight is removed, or both on a tie.
+    fn weigh_diagonals(
+        &self,
+        graph: &[u8],
+        first: (usize, usize),
+        second: (usize, usize),
+    ) -> (usize, usize) {
+        let (mut w1, mut w2) = (0, 0);
+
+        // curves: keep the diagonal that is part of the longer curve
+        let (l1, l2) = (
+            self.curve_length(graph, first.0, first.1),
+            self.curve_length(graph, second.0, second.1),
+        );
+        if l1 > l2 {
+            w1 += l1 - l2;
+        } else {
+            w2 += l2 - l1;
+        }
+
+        // sparse pixels: keep the diagonal of the sparser feature, which is likely the
+        // foreground on a larger background
+        let (s1, s2) = (
+            self.window_component(graph, first.0),
+            self.window_component(graph, second.0),
+        );
+        if s1 < s2 {
+            w1 += s2 - s1;
+        } else {
+            w2 += s1 - s2;
+        }
+
+        // islands: keep a diagonal that is the only connection of one of its pixels
+        if valence(graph, first.0) == 1 || valence(graph, first.1) == 1 {
+            w1 += ISLAND_WEIGHT;
+        }
+        if valence(graph, second.0) == 1 || valence(graph, second.1) == 1 {
+            w2 += ISLAND_WEIGHT;
+        }
+        return (w1, w2);
+    }
+
+    /// Returns the number of pixels connected to idx within the window centered on the 2x2
+    /// block it is part of
+    fn window_component(&self, graph: &[u8], idx: usize) -> usize {
+        let (x, y) = (idx % self.width, idx / self.width);
+        let half = SPARSE_WINDOW / 2 - 1;
+        let (x0, y0) = (x.saturating_sub(half), y.saturating_sub(half));
+        let (x1, y1) = (
+            (x + half + 1).min(self.width - 1),
+            (y + half + 1).min(self.height - 1),
+        );
+        let mut visited = HashSet::new();
+        let mut stack = vec![idx];
+        visited.insert(idx);
+        while let Some(current) = stack.pop() {
+            for (k, offset) in NEIGHBOURS.iter().enumerate() {
+                if !linked(graph, current, k) {
+                    continue;
+                }
+                if let Some(other) = self.offset(current, *offset) {
+                    let (ox, oy) = (other % self.width, other / self.width);
+                    if ox >= x0 && ox <= x1 && oy >= y0 && oy <= y1 && visited.insert(other) {
+                        stack.push(other);
+                    }
+                }
+            }
+        }
+        return visited.len();
+    }
+
+    /// Labels the connected components of the similarity graph, returning the label of every
+    /// pixel and the number of labels
+    fn label_regions(&self, graph: &[u8]) -> (Vec<usize>, usize) {
+        let mut labels = vec![usize::max_value(); self.pixels.len()];
+        let mut count = 0;
+        for start in 0..self.pixels.len() {
+            if labels[start] != usize::max_value() {
+                continue;
+            }
+            labels[start] = count;
+            let mut queue = VecDeque::new();
+            queue.push_back(start);
+            while let Some(current) = queue.pop_front() {
+                for (k, offset) in NEIGHBOURS.iter().enumerate() {
+                    if !linked(graph, current, k) {
+                        continue;
+                    }
+                    if let Some(other) = self.offset(current, *offset) {
+                        if labels[other] == usize::max_value() {
+                            labels[other] = count;
+                            queue.push_back(other);
+                        }
+                    }
+                }
+            }
+            count += 1;
+        }
+        return (labels, count);
+    }
+
+    /// Returns the corners of the cell of pixel idx clockwise on screen, in units of a quarter
+    /// pixel. Each corner of the square pixel is cut along a diagonal the pixel keeps through
+    /// it, or pulled in when the two other pixels there keep the diagonal crossing it.
+    fn cell(&self, graph: &[u8], idx: usize) -> Vec<(i64, i64)> {
+        let (x, y) = ((idx % self.width) as i64, (idx / self.width) as i64);
+        let center = (UNITS * x + UNITS / 2, UNITS * y + UNITS / 2);
+        let mut corners = vec![];
+        for (sx, sy) in [(-1i64, -1i64), (1, -1), (1, 1), (-1, 1)].iter() {
+            let (sx, sy) = (*sx, *sy);
+            let corner = (center.0 + 2 * sx, center.1 + 2 * sy);
+            let own = linked(graph, idx, direction(sx, sy));
+            let crossing = self
+                .offset(idx, (sx as isize, 0))
+                .map_or(false, |side| linked(graph, side, direction(-sx, sy)));
+            if own {
+                corners.push((corner.0 + sy, corner.1 - sx));
+                corners.push((corner.0 - sy, corner.1 + sx));
+            } else if crossing {
+                corners.push((corner.0 - sx, corner.1 - sy));
+            } else {
+                corners.push(corner);
+            }
+        }
+        return corners;
+    }
+
+    /// Walks the edges of the cells between different regions into chains, cut at every
+    /// junction where more than two regions meet
+    fn boundaries(&self, graph: &[u8], labels: &[usize]) -> Vec<Chain> {
+        type Vertex = (i64, i64);
+        let mut edges: HashMap<(Vertex, Vertex), usize> = HashMap::new();
+        for idx in 0..self.pixels.len() {
+            let cell = self.cell(graph, idx);
+            for i in 0..cell.len() {
+                edges.insert((cell[i], cell[(i + 1) % cell.len()]), labels[idx]);
+            }
+        }
+
+        // every boundary between two regions is walked once, along the side of the region
+        // with the lower label
+        let mut primary: Vec<(Vertex, Vertex, usize, Option<usize>)> = edges
+            .iter()
+            .filter_map(|(&(from, to), &owner)| match edges.get(&(to, from)) {
+                None => Some((from, to, owner, None)),
+                Some(&other) if owner < other => Some((from, to, owner, Some(other))),
+                _ => None,
+            })
+            .collect();
+        primary.sort();
+
+        let mut incident: HashMap<Vertex, Vec<usize>> = HashMap::new();
+        let mut outgoing: HashMap<Vertex, Vec<usize>> = HashMap::new();
+        for (index, (from, to, _, _)) in primary.iter().enumerate() {
+            incident.entry(*from).or_insert_with(Vec::new).push(index);
+            incident.entry(*to).or_insert_with(Vec::new).push(index);
+            outgoing.entry(*from).or_insert_with(Vec::new).push(index);
+        }
+        let junction = |vertex: &Vertex| {
+            let edges = &incident[vertex];
+            edges.len() != 2 || {
+                let (a, b) = (&primary[edges[0]], &primary[edges[1]]);
+                (a.2, a.3) != (b.2, b.3)
+            }
+        };
+
+        let mut visited = vec![false; primary.len()];
+        let mut chains = vec![];
+        let walk = |start: usize, visited: &mut Vec<bool>| {
+            let (from, _, owner, neighbour) = primary[start];
+            let mut points = vec![from];
+            let mut current = start;
+            loop {
+                visited[current] = true;
+                let to = primary[current].1;
+                points.push(to);
+                if to == from || junction(&to) {
+                    break;
+                }
+                match outgoing[&to].iter().find(|next| !visited[**next]) {
+                    Some(next) => current = *next,
+                    None => break,
+                }
+            }
+            let closed = points.len() > 2 && points[0] == points[points.len() - 1];
+            if closed {
+                points.pop();
+            }
+            Chain {
+                points,
+                closed,
+                owner,
+                neighbour,
+            }
+        };
+        for index in 0..primary.len() {
+            if !visited[index] && junction(&primary[index].0) {
+                chains.push(walk(index, &mut visited));
+            }
+        }
+        // whatever is left are loops without any junction
+        for index in 0..primary.len() {
+            if !visited[index] {
+                chains.push(walk(index, &mut visited));
+            }
+        }
+        return chains;
+    }
+}
+
+/// Converts an RGB color to YUV, each on a 0..255 scale
+fn yuv(pixel: [u8; 4]) -> (f64, f64, f64) {
+    let (r, g, b) = (pixel[0] as f64, pixel[1] as f64, pixel[2] as f64);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    return (y, 0.492 * (b - y), 0.877 * (r - y));
+}
+
+/// Returns the bit of the neighbour at the given offset
+fn direction(dx: i64, dy: i64) -> usize {
+    NEIGHBOURS
+        .iter()
+        .position(|offset| *offset == (dx as isize, dy as isize))
+        .unwrap()
+}
+
+fn linked(graph: &[u8], idx: usize, k: usize) -> bool {
+    graph[idx] & (1 << k) != 0
+}
+
+fn valence(graph: &[u8], idx: usize) -> usize {
+    graph[idx].count_ones() as usize
+}
+
+/// Joins the chains around a region into closed curves, reversing those the region neighbours
+fn assemble(
+    chains: &[Chain],
+    fitted: &[Vec<Segment>],
+    pieces: &[(usize, bool)],
+) -> Vec<Vec<Segment>> {
+    let oriented = |(index, reversed): (usize, bool)| -> Vec<Segment> {
+        if reversed {
+            fitted[index].iter().rev().map(Segment::reverse).collect()
+        } else {
+            fitted[index].clone()
+        }
+    };
+    let ends = |(index, reversed): (usize, bool)| {
+        let points = &chains[index].points;
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if reversed {
+            (last, first)
+        } else {
+            (first, last)
+        }
+    };
+
+    let mut curves = vec![];
+    let mut used = vec![false; pieces.len()];
+    for start in 0..pieces.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut curve = oriented(pieces[start]);
+        if !chains[pieces[start].0].closed {
+            let (origin, mut end) = ends(pieces[start]);
+            while end != origin {
+                let next = (0..pieces.len()).find(|k| {
+                    !used[*k] && !chains[pieces[*k].0].closed && ends(pieces[*k]).0 == end
+                });
+                match next {
+                    Some(next) => {
+                        used[next] = true;
+                        curve.extend(oriented(pieces[next]));
+                        end = ends(pieces[next]).1;
+                    }
+                    None => break,
+                }
+            }
+        }
+        if !curve.is_empty() {
+            curves.push(curve);
+        }
+    }
+    return curves;
+}
+
+/// Renders a region as an SVG path, with every point mapped through the given transform
+fn render_region(region: &Region, transform: &Transform) -> String {
+    let mut data = vec![];
+    for curve in &region.curves {
+        let origin = transform.apply(curve[0].start());
+        data.push(format!("M {} {}", fixed(origin.x), fixed(origin.y)));
+        for segment in curve {
+            match segment.transform(transform) {
+                Segment::Line { to, .. } => data.push(format!("L {} {}", fixed(to.x), fixed(to.y))),
+                Segment::Cubic { c1, c2, to, .. } => data.push(format!(
+                    "C {} {}, {} {}, {} {}",
+                    fixed(c1.x),
+                    fixed(c1.y),
+                    fixed(c2.x),
+                    fixed(c2.y),
+                    fixed(to.x),
+                    fixed(to.y)
+                )),
+            }
+        }
+        data.push("Z".to_string());
+    }
+    let [r, g, b, a] = region.color;
+    let opacity = if a == 255 {
+        "".to_string()
+    } else {
+        format!(" fill-opacity=\"{}\"", fixed(a as f64 / 255f64))
+    };
+    return format!(
+        "<path d=\"{}\" fill=\"#{:02x}{:02x}{:02x}\"{} stroke=\"none\"/>",
+        data.join(" "),
+        r,
+        g,
+        b,
+        opacity
+    );
+}
@@ -7,16 +7,31 @@ extern crate regex;
 pub mod constants;
 
 pub mod types {
+    pub mod binary_bitmap;
     pub mod bitmap;
+    pub mod centerline;
+    pub mod components;
     pub mod curve;
+    pub mod fit;
     pub mod histogram;
+    pub mod measurements;
+    pub mod morphology;
     pub mod opti;
     pub mod path;
     pub mod point;
+    pub mod polygon;
+    pub mod primitives;
     pub mod quad;
+    pub mod segment;
+    pub mod shape;
+    pub mod shape_index;
+    pub mod simplify;
     pub mod sum;
+    pub mod transform;
 }
 
+pub mod depixelizer;
+
 pub mod posterizer;
 
 pub mod potrace;
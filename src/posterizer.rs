@@ -5,7 +5,9 @@ use num::clamp;
 use potrace::{Potrace, PotraceOptions};
 use std::cmp::Ordering;
 use std::cmp::{max, min};
+use types::curve::fixed;
 use types::histogram::Histogram;
+use types::transform::Transform;
 use utils::set_html_attribute;
 
 /**
@@ -58,7 +60,7 @@ pub struct Posterizer {
 
 impl Posterizer {
     pub fn new(base64: &str, options: Option<Box<PosterizerOptions>>) -> Posterizer {
-        match options {
+        let mut posterizer = match options {
             Some(val) => Posterizer {
                 potrace: match val.potrace {
                     Some(potrace) => Potrace::new(base64, Some(potrace)),
@@ -91,13 +93,22 @@ impl Posterizer {
                 fillStrategy: Some(Box::new(FillStrategies::Dominant)),
                 rangeDistribution: RANGES_AUTO.to_string(),
             },
-        }
+        };
+        posterizer.blackOnWhite = posterizer.potrace.is_black_on_white();
+        return posterizer;
+    }
+
+    /// Returns whether dark pixels are traced on a light background, as detected from the image
+    /// when autoPolarity is set on the potrace options
+    pub fn is_black_on_white(&self) -> bool {
+        self.blackOnWhite
     }
 
     /// Sets parameters. Accepts same object as {Potrace}
     pub fn set_parameters(&mut self, params: PosterizerOptions) {
         if let Some(newParams) = params.potrace {
-            self.potrace.set_parameters(*newParams)
+            self.potrace.set_parameters(*newParams);
+            self.blackOnWhite = self.potrace.is_black_on_white();
         };
         self.steps = match params.steps {
             Some(steps) => Some(steps),
@@ -114,15 +125,15 @@ impl Posterizer {
         self.calculatedThreshold = None;
     }
 
-    /// Returns image as <symbol> tag. Always has viewBox specified
+    /// Returns image as <symbol> tag. Always has viewBox specified, in output units like the
+    /// SVG image
     pub fn get_symbol(&mut self, id: &str) -> String {
-        let width = self.potrace.luminanceData.width;
-        let height = self.potrace.luminanceData.height;
-        let paths = self.get_path_tags(true).join("");
+        let (transform, width, height) = self.potrace.get_output_transform();
+        let paths = self.get_path_tags(true, Some(Box::new(transform))).join("");
         return format!(
             "<symbol viewBox=\"0 0 {width} {height}\" id=\"{id}\">{paths}</symbol>",
-            width = width,
-            height = height,
+            width = fixed(width),
+            height = fixed(height),
             id = id,
             paths = paths
         );
@@ -130,14 +141,15 @@ impl Posterizer {
 
     /// Generates SVG image
     pub fn get_svg(&mut self) -> String {
-        let width = self.potrace.luminanceData.width;
-        let height = self.potrace.luminanceData.height;
+        let (transform, width, height) = self.potrace.get_output_transform();
         let bg = self.get_bg();
-        let tags = self.get_path_tags(false);
+        let tags = self.get_path_tags(false, Some(Box::new(transform)));
         return format!(
-            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} ${height}\" version=\"1.1\">{bg}{tags}</svg>",
-            width=width.to_string(),
-            height=height.to_string(),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {viewWidth} {viewHeight}\" version=\"1.1\">{bg}{tags}</svg>",
+            width=self.potrace.format_length(width),
+            height=self.potrace.format_length(height),
+            viewWidth=fixed(width),
+            viewHeight=fixed(height),
             bg=bg,
             tags=tags.join("")
         );
@@ -157,7 +169,11 @@ impl Posterizer {
 
     /// Running potrace on the image multiple times with different thresholds and returns an array
     /// of path tags
-    fn get_path_tags(&mut self, noFillColor: bool) -> Vec<String> {
+    fn get_path_tags(
+        &mut self,
+        noFillColor: bool,
+        transform: Option<Box<Transform>>,
+    ) -> Vec<String> {
         let mut ranges = self.get_ranges();
         let mut potrace = self.potrace.clone();
         let blackOnWhite = self.blackOnWhite;
@@ -168,7 +184,7 @@ impl Posterizer {
 
         potrace.set_parameters(PotraceOptions {
             blackOnWhite,
-            ..Default::default()
+            ..potrace.get_parameters()
         });
 
         let mut actualPrevLayersOpacity = 0;
@@ -197,13 +213,13 @@ impl Posterizer {
 
             potrace.set_parameters(PotraceOptions {
                 threshold: Some(Box::new(colorStop.value)),
-                ..Default::default()
+                ..potrace.get_parameters()
             });
 
             let mut element = if noFillColor {
-                potrace.get_path_tag(Some(Box::new("".to_string())), None, None)
+                potrace.get_path_tag(Some(Box::new("".to_string())), transform.clone())
             } else {
-                potrace.get_path_tag(None, None, None)
+                potrace.get_path_tag(None, transform.clone())
             };
             element = set_html_attribute(element, "fill-opacity", calculatedOpacity.to_string());
 
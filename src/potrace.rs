@@ -1,24 +1,193 @@
 use base64::decode;
-use constants::{SupportedTurnpolicyValues, COLOR_AUTO, COLOR_TRANSPARENT};
-use types::{bitmap::Bitmap, path::Path, point::Point};
+use constants::{
+    Alignments, Channels, ColorMetrics, Connectivity, CurveCommands, CurveFitters, FillRules,
+    FitModes, PageSizes, PolygonModes, SimplificationMethods, SupportedTurnpolicyValues, Units,
+    Windings, COLOR_AUTO, COLOR_TRANSPARENT,
+};
+use image::ImageFormat;
+use types::curve::{fixed, Curve};
+use types::{
+    binary_bitmap::BinaryBitmap,
+    bitmap::Bitmap,
+    centerline::{self, render_stroke, Stroke},
+    components::Components,
+    fit::{fit_least_squares, fit_pixels},
+    measurements::{self, ShapeMeasurements},
+    morphology::{self, Morphology},
+    path::Path,
+    point::Point,
+    polygon::Polygon,
+    primitives::{recognize, recognize_shape, render_primitive, RecognizedShape},
+    segment::ArcSegment,
+    shape::{group_shapes, Shape},
+    shape_index::ShapeIndex,
+    simplify::simplify,
+    transform::Transform,
+};
+use utils::{page_size_inches, parse_hex_color, read_dpi, unit_suffix, units_per_inch};
+
+/// Removes the backdrop of an image before tracing, by flood filling from its border
+#[derive(Clone)]
+pub struct BackgroundRemoval {
+    /// maximum distance from the border color for a pixel to be background
+    pub tolerance: f64,
+    /// compare colors using this metric, instead of levels of the selected channel
+    pub metric: Option<ColorMetrics>,
+    pub connectivity: Connectivity,
+    /// drop traced paths touching the edge of the image
+    pub drop_edge_paths: bool,
+}
+
+/// Reduces the number of vertices of polygons returned by get_polygons
+#[derive(Clone)]
+pub struct Simplification {
+    pub method: SimplificationMethods,
+    /// maximum deviation, in output units
+    pub tolerance: f64,
+}
+
+/// Traces the middle of lines instead of their outlines, see {@link centerline::trace_centerlines}
+#[derive(Clone)]
+pub struct Centerline {
+    /// branches from a junction to a free end shorter than this are pruned, in pixels
+    pub spur_length: f64,
+    /// maximum distance of the skeleton from the fitted curves, in pixels
+    pub max_error: f64,
+}
+
+impl Default for Centerline {
+    fn default() -> Centerline {
+        Centerline {
+            spur_length: 4f64,
+            max_error: 1f64,
+        }
+    }
+}
+
+/// Traces pixels close to a color rather than pixels past a luminance threshold
+#[derive(Clone)]
+pub struct ColorMatch {
+    /// color to match
+    pub color: [u8; 3],
+    /// maximum distance from the color, in units of the metric
+    pub tolerance: f64,
+    pub metric: ColorMetrics,
+}
+
+impl ColorMatch {
+    /// Creates a color match from a hex color such as "#E4002B"
+    pub fn from_hex(hex: &str, tolerance: f64, metric: ColorMetrics) -> Option<ColorMatch> {
+        parse_hex_color(hex).map(|color| ColorMatch {
+            color,
+            tolerance,
+            metric,
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct PotraceOptions {
     /// how to resolve ambiguities in path decomposition. (default: "minority")
-    turnPolicy: SupportedTurnpolicyValues,
+    pub turnPolicy: SupportedTurnpolicyValues,
     /// suppress speckles of up to this size (default: 2)
-    turdSize: i32,
+    pub turdSize: i32,
+    /// fill holes of up to this size, None meaning turdSize (default: None)
+    pub holeSize: Option<Box<i32>>,
+    /// suppress paths whose bounding box is smaller than this along both axes (default: 0)
+    pub minSize: f64,
+    /// suppress paths with a pixel boundary shorter than this (default: 0)
+    pub minPerimeter: usize,
+    /// morphological operations applied in order to the binary bitmap before tracing (default: [])
+    pub morphology: Vec<Morphology>,
     /// corner threshold parameter (default: 1)
-    alphaMax: i32,
+    pub alphaMax: i32,
     /// turn on/off curve optimization (default: true)
-    optCurve: bool,
+    pub optCurve: bool,
     /// curve optimization tolerance (default: 0.2)
-    optTolerance: f64,
-    threshold: Option<Box<u8>>,
-    blackOnWhite: bool,
-    color: Option<Box<String>>,
-    background: Option<Box<String>>,
-    width: Option<Box<usize>>,
-    height: Option<Box<usize>>,
+    pub optTolerance: f64,
+    /// how pixel boundaries are fitted with curves, alphaMax, optCurve and optTolerance only
+    /// applying to the Potrace fitter (default: Potrace)
+    pub curveFitter: CurveFitters,
+    /// turn off curve optimization regardless of optCurve, like potrace's --longcurve
+    /// (default: false)
+    pub longcurve: bool,
+    pub threshold: Option<Box<u8>>,
+    pub blackOnWhite: bool,
+    /// detect blackOnWhite from the border pixels and histogram of the image (default: false)
+    pub autoPolarity: bool,
+    pub color: Option<Box<String>>,
+    pub background: Option<Box<String>>,
+    /// width of the SVG image, keeping the aspect ratio when height is not set (default: None)
+    pub width: Option<Box<f64>>,
+    /// height of the SVG image, keeping the aspect ratio when width is not set (default: None)
+    pub height: Option<Box<f64>>,
+    /// how the image is scaled when both width and height are set (default: Stretch)
+    pub fit: FitModes,
+    /// horizontal placement of the image when fit does not fill the width (default: Mid)
+    pub alignX: Alignments,
+    /// vertical placement of the image when fit does not fill the height (default: Mid)
+    pub alignY: Alignments,
+    /// affine transform applied to the output coordinates after tracing, e.g. to rotate or flip
+    /// the image. Fitting works in pixels and is not affected. (default: None)
+    pub transform: Option<Box<Transform>>,
+    /// units of the SVG dimensions, width, height and margin, None meaning pixels of the input
+    /// image (default: None)
+    pub units: Option<Box<Units>>,
+    /// resolution of the input image in dots per inch, None meaning the resolution stored in the
+    /// image file, or 72 when there is none (default: None)
+    pub resolution: Option<Box<f64>>,
+    /// size of the page the image is placed on, None meaning the size of the image plus margins
+    /// (default: None)
+    pub pageSize: Option<Box<PageSizes>>,
+    /// top, right, bottom and left margins around the image (default: 0)
+    pub margin: [f64; 4],
+    /// clockwise rotation of the image in degrees, applied before transform (default: 0)
+    pub rotation: f64,
+    /// crop the image to the bounding box of the traced curves (default: false)
+    pub tightBounds: bool,
+    /// how get_polygons turns shapes into polygons (default: Flatten with a tolerance of 0.1)
+    pub polygonMode: PolygonModes,
+    /// simplify polygons returned by get_polygons, keeping their topology (default: None)
+    pub simplification: Option<Box<Simplification>>,
+    /// SVG commands curves are written with (default: Cubic)
+    pub curveCommands: CurveCommands,
+    /// write curves straight within this distance in output units as lines, merging collinear
+    /// lines (default: None)
+    pub lineTolerance: Option<Box<f64>>,
+    /// fill-rule of the SVG path (default: EvenOdd)
+    pub fillRule: FillRules,
+    /// direction of outlines and holes, None keeping the traced direction unless fillRule is
+    /// NonZero, which defaults to OutlinesClockwise (default: None)
+    pub winding: Option<Box<Windings>>,
+    /// write shapes without holes recognized as circles, ellipses, rectangles or regular polygons
+    /// within this tolerance in output units as SVG elements instead of paths (default: None)
+    pub primitives: Option<Box<f64>>,
+    /// fill holes with white, or black when tracing in white, underneath the traced shapes so
+    /// that the result is opaque, like potrace's --opaque (default: false)
+    pub opaque: bool,
+    /// write all shapes as a single path, like potrace's --flat. Otherwise every shape is a path
+    /// of its own, with its holes, inside a group (default: true)
+    pub flat: bool,
+    /// write strokes along the middle of lines, with fill none and the estimated stroke width,
+    /// instead of filled outlines (default: None)
+    pub centerline: Option<Box<Centerline>>,
+    /// add shape-rendering="crispEdges" to the traced paths, keeping pixel exact edges sharp
+    /// (default: false)
+    pub crispEdges: bool,
+    /// channel pixel levels are read from before thresholding (default: Rec709)
+    pub channel: Channels,
+    /// minimum opacity of a pixel to be traced when reading the alpha channel (default: 128)
+    pub alphaThreshold: u8,
+    /// color semi-transparent pixels are composited over before reading levels (default: white)
+    pub matte: [u8; 3],
+    /// trace pixels matching a color instead of thresholding levels (default: None)
+    pub colorMatch: Option<Box<ColorMatch>>,
+    /// connectivity of trace_region_at regions and of connected components (default: Eight)
+    pub connectivity: Connectivity,
+    /// compare colors using this metric when selecting regions, instead of levels (default: None)
+    pub regionMetric: Option<Box<ColorMetrics>>,
+    /// mark pixels connected to the image border and similar to it as background (default: None)
+    pub backgroundRemoval: Option<Box<BackgroundRemoval>>,
 }
 
 impl Default for PotraceOptions {
@@ -26,67 +195,176 @@ impl Default for PotraceOptions {
         return PotraceOptions {
             turnPolicy: SupportedTurnpolicyValues::Minority,
             turdSize: 2,
+            holeSize: None,
+            minSize: 0f64,
+            minPerimeter: 0,
+            morphology: vec![],
             alphaMax: 1,
             optCurve: true,
             optTolerance: 0.2,
+            curveFitter: CurveFitters::Potrace,
+            longcurve: false,
             threshold: None,
             blackOnWhite: true,
+            autoPolarity: false,
             color: Some(Box::new(COLOR_AUTO.to_string())),
             background: Some(Box::new(COLOR_TRANSPARENT.to_string())),
             width: None,
             height: None,
+            fit: FitModes::Stretch,
+            alignX: Alignments::Mid,
+            alignY: Alignments::Mid,
+            transform: None,
+            units: None,
+            resolution: None,
+            pageSize: None,
+            margin: [0f64; 4],
+            rotation: 0f64,
+            tightBounds: false,
+            polygonMode: PolygonModes::Flatten { tolerance: 0.1 },
+            simplification: None,
+            curveCommands: CurveCommands::Cubic,
+            lineTolerance: None,
+            fillRule: FillRules::EvenOdd,
+            winding: None,
+            primitives: None,
+            opaque: false,
+            flat: true,
+            centerline: None,
+            crispEdges: false,
+            channel: Channels::Rec709,
+            alphaThreshold: 128,
+            matte: [255, 255, 255],
+            colorMatch: None,
+            connectivity: Connectivity::Eight,
+            regionMetric: None,
+            backgroundRemoval: None,
         };
     }
 }
+/// Traces a bitmap into curves.
+///
+/// Shapes, polygons, arcs, primitives, measurements and strokes are reported in the same
+/// coordinates as the SVG output, after units, resolution, layout and transform apply; the
+/// connected components stay in pixels, as they describe the raster itself.
 #[derive(Clone)]
 pub struct Potrace {
     pub luminanceData: Bitmap,
+    /// bilevel input, traced as is instead of thresholding luminanceData
+    binary: Option<Box<BinaryBitmap>>,
     pathlist: Vec<Path>, // []
     processed: bool,     // = false
     params: PotraceOptions,
+    /// horizontal and vertical resolution stored in the image file
+    dpi: Option<(f64, f64)>,
 }
 
 impl Potrace {
     pub fn new(base64: &str, options: Option<Box<PotraceOptions>>) -> Potrace {
-        let slice = decode(base64).unwrap().as_slice();
-        let img = image::load_from_memory(slice).unwrap();
-        let bitmap = Bitmap::new(img);
-        match options {
-            Some(val) => Potrace {
-                luminanceData: bitmap,
-                pathlist: vec![],
-                processed: false,
-                params: *val,
+        let bytes = decode(base64).unwrap();
+        let img = image::load_from_memory(&bytes).unwrap();
+        let params = match options {
+            Some(val) => *val,
+            None => PotraceOptions {
+                ..Default::default()
             },
-            None => Potrace {
-                luminanceData: bitmap,
-                pathlist: vec![],
-                processed: false,
-                params: PotraceOptions {
-                    ..Default::default()
-                },
+        };
+        Potrace {
+            luminanceData: Bitmap::with_channel(img, params.channel.clone(), params.matte),
+            binary: None,
+            pathlist: vec![],
+            processed: false,
+            params,
+            dpi: read_dpi(&bytes),
+        }
+    }
+
+    /// Fast path for 1 bit PBM and bilevel grayscale PNG input, loading pixels straight into a
+    /// {@link BinaryBitmap} without computing levels or a histogram. Returns None for any other
+    /// input, which should be loaded with `new` instead.
+    ///
+    /// As no levels are kept, options that read pixel data (channel, colorMatch,
+    /// backgroundRemoval and trace_region_at) have no effect on such an instance.
+    pub fn from_bilevel(base64: &str, options: Option<Box<PotraceOptions>>) -> Option<Potrace> {
+        let bytes = decode(base64).ok()?;
+        let binary = match BinaryBitmap::from_pbm(&bytes) {
+            Some(bm) => bm,
+            None => {
+                if image::guess_format(&bytes).ok()? != ImageFormat::Png {
+                    return None;
+                }
+                BinaryBitmap::from_image(&image::load_from_memory(&bytes).ok()?)?
+            }
+        };
+        let mut potrace = Potrace::from_binary(binary, options);
+        potrace.dpi = read_dpi(&bytes);
+        return Some(potrace);
+    }
+
+    /// Creates an instance tracing an already thresholded bitmap
+    pub fn from_binary(bitmap: BinaryBitmap, options: Option<Box<PotraceOptions>>) -> Potrace {
+        let params = match options {
+            Some(val) => *val,
+            None => PotraceOptions {
+                ..Default::default()
             },
+        };
+        Potrace {
+            luminanceData: Bitmap::blank(bitmap.width, bitmap.height),
+            binary: Some(Box::new(bitmap)),
+            pathlist: vec![],
+            processed: false,
+            params,
+            dpi: None,
         }
     }
 
     /// Sets algorithm parameters
+    /// Returns the current options, to change some of them with set_parameters
+    pub fn get_parameters(&self) -> PotraceOptions {
+        self.params.clone()
+    }
+
     pub fn set_parameters(&mut self, newParams: PotraceOptions) {
         if newParams.color.is_some() || newParams.background.is_some() {
             self.processed = false
         }
+        if newParams.threshold != self.params.threshold
+            || newParams.blackOnWhite != self.params.blackOnWhite
+        {
+            self.processed = false
+        }
+        if newParams.colorMatch.is_some() || self.params.colorMatch.is_some() {
+            self.processed = false
+        }
+        if newParams.backgroundRemoval.is_some() || self.params.backgroundRemoval.is_some() {
+            self.processed = false
+        }
+        if newParams.curveFitter != self.params.curveFitter
+            || newParams.longcurve != self.params.longcurve
+        {
+            self.processed = false
+        }
+        if newParams.matte != self.params.matte {
+            self.luminanceData.set_matte(newParams.matte);
+            self.processed = false
+        }
+        if newParams.channel != self.params.channel {
+            self.luminanceData.set_channel(newParams.channel.clone());
+            self.processed = false
+        }
         self.params = newParams;
     }
 
     /// Returns <symbol> tag. Always has viewBox specified and comes with no fill color,
-    /// so it could be changed with <use> tag
-    pub fn get_symbol(&self, id: &str) -> String {
-        let width = self.luminanceData.width;
-        let height = self.luminanceData.height;
-        let path = self.get_path_tag(None, None, None);
+    /// so it could be changed with <use> tag. The viewBox is in output units, like the SVG image.
+    pub fn get_symbol(&mut self, id: &str) -> String {
+        let (transform, width, height) = self.get_output_transform();
+        let path = self.get_path_tag(None, Some(Box::new(transform)));
         return format!(
             "<symbol viewBox=\"0 0 {width} {height}\" id=\"{id}\">{path}</symbol>",
-            width = width,
-            height = height,
+            width = fixed(width),
+            height = fixed(height),
             id = id,
             path = path
         );
@@ -94,35 +372,138 @@ impl Potrace {
 
     /// Generates SVG image
     pub fn get_svg(&mut self) -> String {
-        let width = match self.params.width.clone() {
-            Some(val) => *val,
-            None => self.luminanceData.width,
-        };
-        let height = match self.params.height.clone() {
-            Some(val) => *val,
-            None => self.luminanceData.height,
-        };
+        let (transform, width, height) = self.get_output_transform();
         let bg = self.get_bg();
-        let path = self.get_path_tag(
-            self.params.color.clone(),
-            match self.params.width.clone() {
-                Some(val) => Some(Box::new(*val / self.luminanceData.width)),
-                None => Some(Box::new(1)),
-            },
-            match self.params.height.clone() {
-                Some(val) => Some(Box::new(*val / self.luminanceData.height)),
-                None => Some(Box::new(1)),
-            },
-        );
+        let path = self.get_path_tag(self.params.color.clone(), Some(Box::new(transform)));
         return format!(
-            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\" version=\"1.1\"> {bg}${path}</svg>",
-            width=width,
-            height=height,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {viewWidth} {viewHeight}\" version=\"1.1\"> {bg}{path}</svg>",
+            width=self.format_length(width),
+            height=self.format_length(height),
+            viewWidth=fixed(width),
+            viewHeight=fixed(height),
             bg=bg,
             path=path
         );
     }
 
+    /// Returns the transform mapping traced curves into the SVG image, along with the width and
+    /// height of the image in the output units.
+    ///
+    /// Pixels are first converted to the output units according to the resolution, then rotated
+    /// and mapped through the transform option, and the result, or with tightBounds just the
+    /// traced curves, moved back to the origin. When a width or height is requested, the image is
+    /// then scaled into it according to fit and aligned according to alignX and alignY. Finally it
+    /// is placed inside the margins, aligned on the page when a page size is set.
+    pub fn get_output_transform(&mut self) -> (Transform, f64, f64) {
+        let (dpi_x, dpi_y) = self.get_resolution();
+        let per_inch = self.get_units_per_inch();
+        let mut user = Transform::scale(per_inch / dpi_x, per_inch / dpi_y)
+            .then(&Transform::rotate(self.params.rotation));
+        if let Some(val) = self.params.transform.clone() {
+            user = user.then(&val);
+        }
+
+        let image = Point::new(
+            self.luminanceData.width as f64,
+            self.luminanceData.height as f64,
+        );
+        let mut bounds = user.apply_to_rect(Point::default(), image);
+        if self.params.tightBounds {
+            self.ensure_processed();
+            if let Some(content) = self.get_content_bounds(&user) {
+                bounds = content;
+            }
+        }
+        let (min, max) = bounds;
+        let (w, h) = (max.x - min.x, max.y - min.y);
+        let base = user.then(&Transform::translate(-min.x, -min.y));
+
+        let (width, height) = match (self.params.width.clone(), self.params.height.clone()) {
+            (Some(width), Some(height)) => (*width, *height),
+            (Some(width), None) if w > 0f64 => (*width, h * *width / w),
+            (None, Some(height)) if h > 0f64 => (w * *height / h, *height),
+            _ => (w, h),
+        };
+        let (sx, sy) = if w > 0f64 && h > 0f64 {
+            (width / w, height / h)
+        } else {
+            (1f64, 1f64)
+        };
+        let (sx, sy) = match self.params.fit {
+            FitModes::Stretch => (sx, sy),
+            FitModes::Contain => (sx.min(sy), sx.min(sy)),
+            FitModes::Cover => (sx.max(sy), sx.max(sy)),
+        };
+
+        let [top, right, bottom, left] = self.params.margin;
+        let (page_width, page_height) = match self.params.pageSize.clone() {
+            Some(page) => {
+                let (pw, ph) = page_size_inches(&page, per_inch);
+                (pw * per_inch, ph * per_inch)
+            }
+            None => (width + left + right, height + top + bottom),
+        };
+        let offset = |align: &Alignments, free: f64| match align {
+            Alignments::Min => 0f64,
+            Alignments::Mid => free / 2f64,
+            Alignments::Max => free,
+        };
+        let tx = left
+            + offset(&self.params.alignX, page_width - left - right - width)
+            + offset(&self.params.alignX, width - w * sx);
+        let ty = top
+            + offset(&self.params.alignY, page_height - top - bottom - height)
+            + offset(&self.params.alignY, height - h * sy);
+        let transform = base
+            .then(&Transform::scale(sx, sy))
+            .then(&Transform::translate(tx, ty));
+        return (transform, page_width, page_height);
+    }
+
+    /// Returns resolution of the input image in dots per inch, horizontally and vertically
+    pub fn get_resolution(&self) -> (f64, f64) {
+        match (self.params.resolution.clone(), self.dpi) {
+            (Some(val), _) => (*val, *val),
+            (None, Some(dpi)) => dpi,
+            (None, None) => (72f64, 72f64),
+        }
+    }
+
+    /// Returns number of output units in an inch, which for pixel output is the resolution
+    fn get_units_per_inch(&self) -> f64 {
+        match self.params.units.clone() {
+            Some(units) => units_per_inch(&units),
+            None => self.get_resolution().0,
+        }
+    }
+
+    /// Formats a length in the output units for SVG width and height attributes
+    pub fn format_length(&self, value: f64) -> String {
+        match self.params.units.clone() {
+            Some(units) => format!("{}{}", fixed(value), unit_suffix(&units)),
+            None => fixed(value),
+        }
+    }
+
+    /// Returns the exact bounding box of the traced curves after the given transform, or None
+    /// when nothing was traced
+    fn get_content_bounds(&self, transform: &Transform) -> Option<(Point, Point)> {
+        let mut bounds: Option<(Point, Point)> = None;
+        for path in &self.pathlist {
+            for segment in path.curve.segments() {
+                let (lo, hi) = segment.transform(transform).bounds();
+                bounds = Some(match bounds {
+                    Some((min, max)) => (
+                        Point::new(min.x.min(lo.x), min.y.min(lo.y)),
+                        Point::new(max.x.max(hi.x), max.y.max(hi.y)),
+                    ),
+                    None => (lo, hi),
+                });
+            }
+        }
+        return bounds;
+    }
+
     pub fn get_bg(&self) -> String {
         let bg = *self.params.background.clone().unwrap();
         if bg == COLOR_TRANSPARENT {
@@ -135,19 +516,19 @@ impl Potrace {
         }
     }
 
-    /// Generates just <path> tag without rest of the SVG file
+    /// Generates just <path> tag without rest of the SVG file, with curves mapped through the
+    /// given transform
     pub fn get_path_tag(
         &mut self,
         fillColor: Option<Box<String>>,
-        x: Option<Box<usize>>,
-        y: Option<Box<usize>>,
+        transform: Option<Box<Transform>>,
     ) -> String {
         let color = match fillColor {
             Some(val) => *val,
             None => *self.params.color.clone().unwrap(),
         };
         let fill = if color == COLOR_AUTO {
-            if self.params.blackOnWhite {
+            if self.is_black_on_white() {
                 "black".to_string()
             } else {
                 "white".to_string()
@@ -156,73 +537,712 @@ impl Potrace {
             color
         };
 
-        if !self.processed {
-            self.bmToPathlist();
-            self.processPath();
-            self.processed = true;
+        let transform = match transform {
+            Some(val) => *val,
+            None => Transform::identity(),
+        };
+        if self.params.centerline.is_some() {
+            return self.get_stroke_tag(&fill, &transform);
         }
+        self.ensure_processed();
 
-        let mut paths = Vec::with_capacity(self.pathlist.len());
-        let width = match x {
-            Some(val) => *val as f64,
-            None => 1f64,
+        // a mirroring transform turns clockwise curves counterclockwise
+        let mirrored = transform.a * transform.d - transform.b * transform.c < 0f64;
+        let reverse = mirrored && outline_winding(&self.params).is_some();
+        let render = |curve: &Curve, params: &PotraceOptions| {
+            let lineTolerance = params.lineTolerance.clone().map(|val| *val);
+            // pixel exact corners end halfway along their edges, merged back into single lines
+            let lineTolerance = match params.curveFitter {
+                CurveFitters::PixelExact => Some(lineTolerance.unwrap_or(1e-9)),
+                _ => lineTolerance,
+            };
+            match (&params.curveCommands, lineTolerance) {
+                (CurveCommands::Cubic, None) => curve.render_curve(&transform),
+                (commands, lineTolerance) => {
+                    curve.render_segments(&transform, commands, lineTolerance)
+                }
+            }
         };
-        let height = match y {
-            Some(val) => *val as f64,
-            None => 1f64,
+        let rendering = if self.params.crispEdges {
+            " shape-rendering=\"crispEdges\""
+        } else {
+            ""
         };
-        for path in &self.pathlist {
-            paths.push(path.curve.render_curve(width, height))
+
+        // curves of every path element, one per shape unless flat
+        let mut elements = vec![];
+        let mut groups: Vec<Vec<Curve>> = vec![];
+        if self.params.primitives.is_some() || !self.params.flat {
+            let tolerance = self
+                .params
+                .primitives
+                .clone()
+                .map(|tolerance| self.pixel_tolerance(*tolerance));
+            for shape in self.get_pixel_shapes() {
+                let primitive = match tolerance {
+                    Some(tolerance) if shape.holes.is_empty() => {
+                        recognize(&shape.outline.curve, tolerance)
+                    }
+                    _ => None,
+                };
+                match primitive {
+                    Some(primitive) => {
+                        elements.push(render_primitive(&primitive, &transform, &fill))
+                    }
+                    None => {
+                        let mut curves = vec![shape.outline.curve];
+                        curves.extend(shape.holes.into_iter().map(|hole| hole.curve));
+                        groups.push(curves);
+                    }
+                }
+            }
+            if self.params.flat {
+                groups = vec![groups.concat()];
+            }
+        } else {
+            groups.push(
+                self.pathlist
+                    .iter()
+                    .map(|path| path.curve.clone())
+                    .collect(),
+            );
         }
+
+        let fillRule = match self.params.fillRule {
+            FillRules::EvenOdd => "evenodd",
+            FillRules::NonZero => "nonzero",
+        };
+        let mut paths = vec![];
+        for group in groups {
+            let group: Vec<String> = group
+                .into_iter()
+                .map(|mut curve| {
+                    if reverse {
+                        curve.reverse();
+                    }
+                    render(&curve, &self.params)
+                })
+                .collect();
+            if !group.is_empty() {
+                paths.push(group.join(" "));
+            }
+        }
+        let mut tag = if self.params.flat {
+            let mut tag = elements.join("");
+            if !paths.is_empty() || elements.is_empty() {
+                tag.push_str(&format!(
+                    "<path d=\"{paths}\" stroke=\"none\" fill=\"{fill}\" fill-rule=\"{fillRule}\"{rendering}/>",
+                    paths = paths.join(" "),
+                    fill = fill,
+                    fillRule = fillRule,
+                    rendering = rendering
+                ));
+            }
+            tag
+        } else {
+            let paths: Vec<String> = paths
+                .iter()
+                .map(|path| format!("<path d=\"{}\"/>", path))
+                .collect();
+            format!(
+                "<g stroke=\"none\" fill=\"{fill}\" fill-rule=\"{fillRule}\"{rendering}>{paths}{elements}</g>",
+                fill = fill,
+                fillRule = fillRule,
+                rendering = rendering,
+                paths = paths.join(""),
+                elements = elements.join("")
+            )
+        };
+
+        if self.params.opaque {
+            // holes filled underneath the traced shapes, all running the same way so that
+            // holes of islands inside holes stay filled
+            let holes: Vec<String> = self
+                .pathlist
+                .iter()
+                .filter(|path| path.sign == "-")
+                .map(|path| {
+                    let mut curve = path.curve.clone();
+                    curve.orient(!mirrored);
+                    render(&curve, &self.params)
+                })
+                .collect();
+            if !holes.is_empty() {
+                let opaque = if fill == "white" { "black" } else { "white" };
+                tag = format!(
+                    "<path d=\"{holes}\" stroke=\"none\" fill=\"{opaque}\" fill-rule=\"nonzero\"{rendering}/>{tag}",
+                    holes = holes.join(" "),
+                    opaque = opaque,
+                    rendering = rendering,
+                    tag = tag
+                );
+            }
+        }
+        return tag;
+    }
+
+    /// Returns a group of stroked paths, one per centerline
+    fn get_stroke_tag(&mut self, stroke: &str, transform: &Transform) -> String {
+        let scale = (transform.a * transform.d - transform.b * transform.c)
+            .abs()
+            .sqrt();
+        let paths: Vec<String> = self
+            .trace_strokes()
+            .iter()
+            .map(|line| {
+                format!(
+                    "<path d=\"{path}\" stroke-width=\"{width}\"/>",
+                    path = render_stroke(line, transform),
+                    width = fixed(line.width * scale)
+                )
+            })
+            .collect();
         return format!(
-            "<path d=\"{paths}\" stroke=\"none\" fill=\"{fill}\" fill-rule=\"evenodd\"/>",
-            paths = paths.join(" "),
-            fill = fill
+            "<g fill=\"none\" stroke=\"{stroke}\" stroke-linecap=\"round\" stroke-linejoin=\"round\">{paths}</g>",
+            stroke = stroke,
+            paths = paths.join("")
         );
     }
 
-    /// Creating a new {@link Path} for every group of black pixels.
-    fn bmToPathlist(&mut self) {
-        let threshold = match self.params.threshold.clone() {
-            Some(_val) => *self
+    /// Traces the middle of every line in the same binary bitmap that gets outlined, according
+    /// to the centerline parameters or their defaults
+    pub fn get_strokes(&mut self) -> Vec<Stroke> {
+        let (transform, _, _) = self.get_output_transform();
+        return self
+            .trace_strokes()
+            .iter()
+            .map(|stroke| stroke.transform(&transform))
+            .collect();
+    }
+
+    /// Traces the centerlines in pixel coordinates
+    fn trace_strokes(&mut self) -> Vec<Stroke> {
+        let params = match self.params.centerline.clone() {
+            Some(val) => *val,
+            None => Centerline::default(),
+        };
+        let blackMap = self.generate_black_map();
+        return centerline::trace_centerlines(
+            &blackMap.to_data(),
+            blackMap.width,
+            blackMap.height,
+            params.spur_length,
+            params.max_error,
+        );
+    }
+
+    /// Traces the image unless the path list is up to date with the parameters
+    fn ensure_processed(&mut self) {
+        if !self.processed {
+            self.bmToPathlist();
+            self.processPath();
+            self.processed = true;
+        }
+    }
+
+    /// Returns the traced paths grouped into shapes, every hole going with the outline it is in
+    pub fn get_shapes(&mut self) -> Vec<Shape> {
+        let (transform, _, _) = self.get_output_transform();
+        return self
+            .get_pixel_shapes()
+            .iter()
+            .map(|shape| shape.transform(&transform))
+            .collect();
+    }
+
+    /// Groups the traced paths into shapes in pixel coordinates
+    fn get_pixel_shapes(&mut self) -> Vec<Shape> {
+        self.ensure_processed();
+        return group_shapes(self.pathlist.clone());
+    }
+
+    /// Returns a spatial index over the traced shapes for fast {@link ShapeIndex::shape_at}
+    /// queries
+    pub fn get_shape_index(&mut self) -> ShapeIndex {
+        ShapeIndex::new(self.get_shapes())
+    }
+
+    /// Returns every traced shape as polygons with holes, taken from the curves or the optimal
+    /// polygons according to polygonMode, and simplified when simplification is set
+    pub fn get_polygons(&mut self) -> Vec<Polygon> {
+        let mode = self.params.polygonMode.clone();
+        let mut polygons: Vec<Polygon> = self
+            .get_shapes()
+            .iter()
+            .map(|shape| match mode {
+                PolygonModes::Flatten { tolerance } => Polygon::from_curves(shape, tolerance),
+                PolygonModes::Optimal => Polygon::from_optimal_polygons(shape),
+            })
+            .collect();
+        if let Some(simplification) = self.params.simplification.clone() {
+            polygons = polygons
+                .iter()
+                .map(|polygon| simplify(polygon, &simplification.method, simplification.tolerance))
+                .collect();
+        }
+        return polygons;
+    }
+
+    /// Converts every traced curve to lines and circular arcs within `tolerance`, for CNC
+    /// controllers taking G2/G3 arcs
+    pub fn get_arcs(&mut self, tolerance: f64) -> Vec<Vec<ArcSegment>> {
+        self.ensure_processed();
+        let (transform, _, _) = self.get_output_transform();
+        return self
+            .pathlist
+            .iter()
+            .map(|path| {
+                path.curve
+                    .segments()
+                    .iter()
+                    .flat_map(|segment| segment.transform(&transform).to_arcs(tolerance))
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Recognizes the outline and holes of every traced shape as circles, ellipses, rectangles
+    /// or regular polygons
+    pub fn get_primitives(&mut self, tolerance: f64) -> Vec<RecognizedShape> {
+        return self
+            .get_shapes()
+            .iter()
+            .map(|shape| recognize_shape(shape, tolerance))
+            .collect();
+    }
+
+    /// Converts a distance in output units into pixels, by the average scale of the output
+    /// transform
+    fn pixel_tolerance(&mut self, tolerance: f64) -> f64 {
+        let (transform, _, _) = self.get_output_transform();
+        let scale = (transform.a * transform.d - transform.b * transform.c)
+            .abs()
+            .sqrt();
+        if scale > 0f64 {
+            tolerance / scale
+        } else {
+            tolerance
+        }
+    }
+
+    /// Measures every traced shape. See {@link measurements::measure_shapes} for what is
+    /// measured.
+    pub fn get_measurements(&mut self) -> Vec<ShapeMeasurements> {
+        measurements::measure_shapes(&self.get_shapes())
+    }
+
+    /// Returns measurements of every traced shape as CSV, one row per shape
+    pub fn get_measurements_csv(&mut self) -> String {
+        measurements::to_csv(&self.get_measurements())
+    }
+
+    /// Returns measurements of every traced shape as a JSON array
+    pub fn get_measurements_json(&mut self) -> String {
+        measurements::to_json(&self.get_measurements())
+    }
+
+    /// Returns the threshold levels are compared against when generating the binary bitmap
+    fn get_threshold(&mut self) -> u8 {
+        if self.params.channel == Channels::Alpha {
+            // alpha levels are stored inverted, opaque pixels being the darkest
+            return 255 - self.params.alphaThreshold;
+        }
+        match self.params.threshold.clone() {
+            Some(_val) => self
                 .luminanceData
                 .histogram
                 .auto_threshold(None, None)
-                .unwrap(),
-            None => vec![128u8],
-        };
-        let blackOnWhite = self.params.blackOnWhite.clone();
-        let mut blackMap = self.luminanceData.generate_binary_bitmap(blackOnWhite, threshold[0]);
-        let mut currentPoint: Option<Box<Point>> = Some(Box::new(Point::new(0f64, 0f64)));
+                .unwrap()[0],
+            None => 128u8,
+        }
+    }
+
+    /// Returns whether dark pixels are traced on a light background. With autoPolarity this is
+    /// the decision made from the image, otherwise the blackOnWhite parameter.
+    pub fn is_black_on_white(&mut self) -> bool {
+        if !self.params.autoPolarity {
+            return self.params.blackOnWhite;
+        }
+        if let Some(binary) = &self.binary {
+            return binary.detect_black_on_white();
+        }
+        let threshold = self.get_threshold();
+        return self.luminanceData.detect_black_on_white(threshold);
+    }
+
+    /// Creating a new {@link Path} for every group of black pixels.
+    fn bmToPathlist(&mut self) {
+        let mut blackMap = self.generate_black_map();
 
         // Clear path list
-        self.pathlist = vec![];
+        self.pathlist = self.decompose(&mut blackMap);
+
+        if let Some(removal) = self.params.backgroundRemoval.clone() {
+            if removal.drop_edge_paths {
+                let width = self.luminanceData.width as f64;
+                let height = self.luminanceData.height as f64;
+                // the holes and islands of a dropped path go with it, or the holes would be
+                // filled on their own
+                let mut dropped: Vec<Path> = vec![];
+                let mut kept = vec![];
+                for path in self.pathlist.drain(..) {
+                    let touches = path.min_x <= 0f64
+                        || path.min_y <= 0f64
+                        || path.max_x >= width
+                        || path.max_y >= height;
+                    if touches || is_nested(&path, &dropped) {
+                        dropped.push(path);
+                    } else {
+                        kept.push(path);
+                    }
+                }
+                self.pathlist = kept;
+            }
+        }
+    }
+
+    /// Generates the binary bitmap that gets traced, with morphology applied
+    fn generate_black_map(&mut self) -> BinaryBitmap {
+        let blackOnWhite = self.is_black_on_white();
+        let mut blackMap = match self.binary.clone() {
+            Some(binary) => {
+                let mut bm = *binary;
+                if !blackOnWhite {
+                    bm.invert();
+                }
+                bm
+            }
+            None => self.threshold_bitmap(blackOnWhite),
+        };
+        for step in &self.params.morphology {
+            let (width, height) = (blackMap.width, blackMap.height);
+            let data = morphology::apply(&blackMap.to_data(), width, height, step);
+            blackMap = BinaryBitmap::from_data(&data, width, height);
+        }
+        return blackMap;
+    }
+
+    /// Labels connected components of black pixels in the same binary bitmap that gets traced,
+    /// measuring area, bounding box, centroid, perimeter and Euler number of each
+    pub fn connected_components(&mut self) -> Components {
+        let blackMap = self.generate_black_map();
+        return blackMap.connected_components(&self.params.connectivity);
+    }
+
+    /// Thresholds luminanceData, or matches it against a color, and clears the background
+    fn threshold_bitmap(&mut self, blackOnWhite: bool) -> BinaryBitmap {
+        let threshold = self.get_threshold();
+        let mut blackMap = match self.params.colorMatch.clone() {
+            Some(val) => {
+                self.luminanceData
+                    .generate_color_bitmap(val.color, val.tolerance, &val.metric)
+            }
+            None => self
+                .luminanceData
+                .generate_binary_bitmap(blackOnWhite, threshold),
+        };
+        if let Some(removal) = self.params.backgroundRemoval.clone() {
+            let background = self.luminanceData.find_background(
+                removal.tolerance,
+                &removal.connectivity,
+                removal.metric.as_ref(),
+            );
+            blackMap.clear_mask(&background);
+        }
+        return blackMap;
+    }
+
+    /// Creates a {@link Path} for every group of black pixels of a binary bitmap, suppressing
+    /// speckles and holes according to the path policies. The bitmap is left blank.
+    fn decompose(&self, blackMap: &mut BinaryBitmap) -> Vec<Path> {
+        let original = blackMap.clone();
+        let mut pathlist = vec![];
+        let mut dropped: Vec<Path> = vec![];
+        let mut currentPoint = blackMap.find_next(Point::new(0f64, 0f64));
 
         while currentPoint.is_some() {
             let point = *currentPoint.unwrap();
-            let path = blackMap.find_path(point, self.params.turnPolicy.clone());
-            blackMap.xor_path(path);
+            let sign = if original.get(point.x, point.y) {
+                "+"
+            } else {
+                "-"
+            };
+            let path = blackMap.find_path(point, sign, self.params.turnPolicy.clone());
+            blackMap.xor_path(&path);
 
-            if path.area > self.params.turdSize as f64 {
-                self.pathlist.push(path)
+            // anything nested in a dropped path goes with it, so that filling a small hole
+            // does not leave the islands inside it behind as holes
+            if !is_nested(&path, &dropped) && self.keep_path(&path) {
+                pathlist.push(path)
+            } else {
+                dropped.push(path)
             }
             currentPoint = blackMap.find_next(point)
         }
+        return pathlist;
+    }
+
+    /// Applies the speckle and hole policies to a path
+    fn keep_path(&self, path: &Path) -> bool {
+        let minArea = if path.sign == "+" {
+            self.params.turdSize
+        } else {
+            match self.params.holeSize.clone() {
+                Some(val) => *val,
+                None => self.params.turdSize,
+            }
+        };
+        let size = (path.max_x - path.min_x).max(path.max_y - path.min_y);
+        return path.area > minArea as f64
+            && size >= self.params.minSize
+            && path.len >= self.params.minPerimeter;
     }
 
     /// Processes path list created by _bmToPathlist method creating and optimizing {@link Curve}'s
-    fn processPath(&self) {
-        for i in 0..self.pathlist.len() {
-            let mut path = self.pathlist[i].clone();
+    fn processPath(&mut self) {
+        let params = self.params.clone();
+        for path in self.pathlist.iter_mut() {
+            process_path(path, &params);
+        }
+    }
+
+    /// Flood fills from the pixel at (x, y) and traces just the connected region it reaches.
+    ///
+    /// Pixels join the region when within `tolerance` of the seed pixel, compared by level of
+    /// the selected channel, or by color when regionMetric is set. Bilevel input is compared
+    /// by value alone, so that the tolerance does not apply.
+    pub fn trace_region_at(&mut self, x: usize, y: usize, tolerance: f64) -> Vec<Shape> {
+        let metric = self.params.regionMetric.clone();
+        let mut region = match self.binary {
+            // bilevel input has no levels to compare, so the region is every pixel reachable
+            // with the same value as the seed
+            Some(ref binary) => {
+                if x >= binary.width || y >= binary.height {
+                    return vec![];
+                }
+                let width = binary.width;
+                let value = binary.get(x as f64, y as f64);
+                let mask = self.luminanceData.flood_fill(
+                    &[y * width + x],
+                    &self.params.connectivity,
+                    |idx| binary.get((idx % width) as f64, (idx / width) as f64) == value,
+                );
+                BinaryBitmap::from_data(&mask, width, binary.height)
+            }
+            None => self.luminanceData.select_region(
+                x,
+                y,
+                tolerance,
+                &self.params.connectivity,
+                metric.as_ref().map(|val| &**val),
+            ),
+        };
+        let mut paths = self.decompose(&mut region);
+        for path in paths.iter_mut() {
+            process_path(path, &self.params);
+        }
+        let (transform, _, _) = self.get_output_transform();
+        return group_shapes(paths)
+            .iter()
+            .map(|shape| shape.transform(&transform))
+            .collect();
+    }
+}
+
+/// Returns whether the first pixel of a path is inside any of the given paths. Paths come out
+/// of decomposition before the paths nested in them, so this finds the holes and islands of
+/// paths seen earlier.
+fn is_nested(path: &Path, outers: &[Path]) -> bool {
+    let center = path.first_pixel_center();
+    let (x, y) = (center.x, center.y);
+    outers.iter().any(|outer| {
+        x > outer.min_x
+            && x < outer.max_x
+            && y > outer.min_y
+            && y < outer.max_y
+            && outer.contains(x, y)
+    })
+}
+
+/// Fits and optimizes the {@link Curve} of a single path
+fn process_path(path: &mut Path, params: &PotraceOptions) {
+    let mut curve = match params.curveFitter {
+        CurveFitters::Potrace => {
             let mut curve = path.calc_sums().calc_lon().best_polygon().adjust_vertices();
             if path.sign == "-" {
                 curve.reverse()
             }
-            curve.smooth(self.params.alphaMax as f64);
-            if self.params.optCurve {
-                curve.optimize_curve(self.params.optTolerance)
+            curve.smooth(params.alphaMax as f64);
+            if params.optCurve && !params.longcurve {
+                curve.optimize_curve(params.optTolerance)
+            }
+            curve
+        }
+        CurveFitters::PixelExact => {
+            let mut curve = fit_pixels(path);
+            if path.sign == "-" {
+                curve.reverse()
+            }
+            curve
+        }
+        CurveFitters::LeastSquares { max_error } => {
+            let mut curve = fit_least_squares(path, max_error);
+            if path.sign == "-" {
+                curve.reverse()
+            }
+            curve
+        }
+    };
+    if let Some(clockwise) = outline_winding(params) {
+        curve.orient((path.sign == "+") == clockwise);
+    }
+    path.curve = curve;
+}
+
+/// Returns whether outlines should run clockwise, or None to keep the traced direction
+fn outline_winding(params: &PotraceOptions) -> Option<bool> {
+    match (params.winding.clone(), &params.fillRule) {
+        (Some(winding), _) => Some(*winding == Windings::OutlinesClockwise),
+        (None, FillRules::NonZero) => Some(true),
+        (None, FillRules::EvenOdd) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a 4x8 rectangle at (1, 1) of a 10x10 bitmap
+    fn rectangle() -> BinaryBitmap {
+        let mut data = vec![0u8; 100];
+        for y in 1..9 {
+            for x in 1..5 {
+                data[y * 10 + x] = 1;
             }
         }
-        return ();
+        BinaryBitmap::from_data(&data, 10, 10)
+    }
+
+    #[test]
+    fn traces_a_shape_away_from_the_origin() {
+        for curveFitter in vec![
+            CurveFitters::PixelExact,
+            CurveFitters::LeastSquares { max_error: 1f64 },
+        ] {
+            let mut potrace = Potrace::from_binary(
+                rectangle(),
+                Some(Box::new(PotraceOptions {
+                    turdSize: 0,
+                    curveFitter,
+                    ..Default::default()
+                })),
+            );
+            let shapes = potrace.get_shapes();
+            assert_eq!(shapes.len(), 1);
+            let outline = &shapes[0].outline;
+            assert_eq!(outline.sign, "+");
+            assert_eq!(shapes[0].holes.len(), 0);
+            assert_eq!(
+                (outline.min_x, outline.min_y, outline.max_x, outline.max_y),
+                (1f64, 1f64, 5f64, 9f64)
+            );
+            assert!(!potrace.get_svg().contains("M 0 0"));
+        }
+    }
+
+    #[test]
+    fn reports_shapes_in_output_coordinates() {
+        let mut potrace = Potrace::from_binary(
+            rectangle(),
+            Some(Box::new(PotraceOptions {
+                turdSize: 0,
+                curveFitter: CurveFitters::PixelExact,
+                transform: Some(Box::new(Transform::scale(2f64, 2f64))),
+                ..Default::default()
+            })),
+        );
+        let shapes = potrace.get_shapes();
+        assert_eq!(shapes.len(), 1);
+        let outline = &shapes[0].outline;
+        assert_eq!(
+            (outline.min_x, outline.min_y, outline.max_x, outline.max_y),
+            (2f64, 2f64, 10f64, 18f64)
+        );
+        assert!(potrace
+            .get_symbol("a")
+            .starts_with("<symbol viewBox=\"0 0 20 20\""));
+        let measurements = potrace.get_measurements();
+        assert!((measurements[0].area - 128f64).abs() < 1e-9);
+        let strokes = potrace.get_strokes();
+        assert!(strokes
+            .iter()
+            .flat_map(|stroke| stroke.points.iter())
+            .all(|point| point.x >= 2f64
+                && point.x <= 10f64
+                && point.y >= 2f64
+                && point.y <= 18f64));
+    }
+
+    #[test]
+    fn reports_results_in_the_output_units() {
+        // 144 dpi input written in points, half a point per pixel
+        let mut potrace = Potrace::from_binary(
+            rectangle(),
+            Some(Box::new(PotraceOptions {
+                turdSize: 0,
+                curveFitter: CurveFitters::PixelExact,
+                units: Some(Box::new(Units::Pt)),
+                resolution: Some(Box::new(144f64)),
+                ..Default::default()
+            })),
+        );
+        assert!(potrace
+            .get_symbol("a")
+            .starts_with("<symbol viewBox=\"0 0 5 5\""));
+        let measurements = potrace.get_measurements();
+        assert!((measurements[0].area - 8f64).abs() < 1e-9);
+        let polygons = potrace.get_polygons();
+        assert!(polygons[0]
+            .outline
+            .iter()
+            .all(|point| point.x >= 0.5 && point.x <= 2.5 && point.y >= 0.5 && point.y <= 4.5));
+    }
+
+    #[test]
+    fn drops_the_holes_of_edge_paths() {
+        // a frame on the edge with a hole and an island in it, and a square clear of the edge
+        let mut data = vec![0u8; 30 * 20];
+        for y in 0..20 {
+            for x in 0..20 {
+                let hole = x >= 5 && x < 15 && y >= 5 && y < 15;
+                let island = x >= 8 && x < 12 && y >= 8 && y < 12;
+                data[y * 30 + x] = (!hole || island) as u8;
+            }
+        }
+        for y in 5..10 {
+            for x in 23..27 {
+                data[y * 30 + x] = 1;
+            }
+        }
+        let mut potrace = Potrace::from_binary(
+            BinaryBitmap::from_data(&data, 30, 20),
+            Some(Box::new(PotraceOptions {
+                curveFitter: CurveFitters::PixelExact,
+                backgroundRemoval: Some(Box::new(BackgroundRemoval {
+                    tolerance: 0f64,
+                    metric: None,
+                    connectivity: Connectivity::Four,
+                    drop_edge_paths: true,
+                })),
+                ..Default::default()
+            })),
+        );
+        let shapes = potrace.get_shapes();
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].holes.len(), 0);
+        assert_eq!(
+            (shapes[0].outline.min_x, shapes[0].outline.min_y),
+            (23f64, 5f64)
+        );
     }
 }
@@ -0,0 +1,442 @@
+use super::components::{label_components, Components};
+use super::path::Path;
+use super::point::Point;
+use constants::{Connectivity, SupportedTurnpolicyValues};
+use image::DynamicImage;
+
+const WORD_BITS: usize = 64;
+
+/// return the bit of a word holding the pixel in column x
+fn bit(x: usize) -> u64 {
+    1u64 << (WORD_BITS - 1 - x % WORD_BITS)
+}
+
+/// Represents a bitmap where each pixel is a single bit, 1 being black
+///
+/// Pixels are packed into 64 bit words, every scanline starting at a new word and the leftmost
+/// pixel being the most significant bit, like potrace's `potrace_bitmap_t`. Bits past the width
+/// of a scanline are always 0. Used internally for path decomposition.
+#[derive(Clone)]
+pub struct BinaryBitmap {
+    pub width: usize,
+    pub height: usize,
+    /// number of words per scanline
+    pub dy: usize,
+    pub words: Vec<u64>,
+}
+
+impl BinaryBitmap {
+    /// Creates a blank (all white) bitmap
+    pub fn new(width: usize, height: usize) -> BinaryBitmap {
+        let dy = (width + WORD_BITS - 1) / WORD_BITS;
+        BinaryBitmap {
+            width,
+            height,
+            dy,
+            words: vec![0u64; dy * height],
+        }
+    }
+
+    /// Packs one byte per pixel data, any non zero value being black
+    pub fn from_data(data: &[u8], width: usize, height: usize) -> BinaryBitmap {
+        let mut bm = BinaryBitmap::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if data[y * width + x] != 0 {
+                    bm.words[y * bm.dy + x / WORD_BITS] |= bit(x);
+                }
+            }
+        }
+        return bm;
+    }
+
+    /// Unpacks the bitmap into one byte per pixel, 1 for black and 0 for white
+    pub fn to_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                data.push(if self.at(x, y) { 1u8 } else { 0u8 });
+            }
+        }
+        return data;
+    }
+
+    /// Loads a plain (P1) or raw (P4) PBM image. Raw scanlines are copied straight into words.
+    pub fn from_pbm(bytes: &[u8]) -> Option<BinaryBitmap> {
+        if bytes.len() < 2 || bytes[0] != b'P' || (bytes[1] != b'1' && bytes[1] != b'4') {
+            return None;
+        }
+        let raw = bytes[1] == b'4';
+        let mut pos = 2;
+        let mut header = [0usize; 2];
+        for value in header.iter_mut() {
+            // skip whitespace and comments
+            while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+                if bytes[pos] == b'#' {
+                    while pos < bytes.len() && bytes[pos] != b'\n' {
+                        pos += 1;
+                    }
+                } else {
+                    pos += 1;
+                }
+            }
+            let start = pos;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            *value = std::str::from_utf8(&bytes[start..pos]).ok()?.parse().ok()?;
+        }
+        let [width, height] = header;
+        // check the header against the data before allocating, raw scanlines taking a bit per
+        // pixel and plain ones at least a byte
+        let size = if raw {
+            // a single whitespace byte separates the header from the data
+            pos += 1;
+            ((width + 7) / 8).checked_mul(height)?
+        } else {
+            width.checked_mul(height)?
+        };
+        if bytes.len().saturating_sub(pos) < size {
+            return None;
+        }
+        let mut bm = BinaryBitmap::new(width, height);
+
+        if raw {
+            let stride = (width + 7) / 8;
+            for y in 0..height {
+                let row = &bytes[pos + y * stride..pos + (y + 1) * stride];
+                for (b, byte) in row.iter().enumerate() {
+                    bm.words[y * bm.dy + b / 8] |= (*byte as u64) << (56 - (b % 8) * 8);
+                }
+            }
+            bm.clear_padding();
+        } else {
+            let mut idx = 0;
+            while idx < width * height {
+                let byte = *bytes.get(pos)?;
+                pos += 1;
+                match byte {
+                    b'0' => idx += 1,
+                    b'1' => {
+                        bm.put(idx % width, idx / width, true);
+                        idx += 1
+                    }
+                    _ => {}
+                }
+            }
+        }
+        return Some(bm);
+    }
+
+    /// Loads a bilevel grayscale image, such as a decoded 1 bit PNG, without computing any
+    /// levels or histogram. Returns None when the image has anything but pure black and white.
+    pub fn from_image(image: &DynamicImage) -> Option<BinaryBitmap> {
+        let gray = match image {
+            DynamicImage::ImageLuma8(gray) => gray,
+            _ => return None,
+        };
+        let (width, height) = (gray.width() as usize, gray.height() as usize);
+        let mut bm = BinaryBitmap::new(width, height);
+        for (x, y, pixel) in gray.enumerate_pixels() {
+            match pixel[0] {
+                0 => bm.put(x as usize, y as usize, true),
+                255 => {}
+                _ => return None,
+            }
+        }
+        return Some(bm);
+    }
+
+    /// Returns whether the pixel at (x, y) is black. Pixels outside of the bitmap are white.
+    pub fn get(&self, x: f64, y: f64) -> bool {
+        if x < 0f64 || y < 0f64 || x >= self.width as f64 || y >= self.height as f64 {
+            return false;
+        }
+        return self.at(x as usize, y as usize);
+    }
+
+    fn at(&self, x: usize, y: usize) -> bool {
+        self.words[y * self.dy + x / WORD_BITS] & bit(x) != 0
+    }
+
+    /// Sets the pixel at (x, y) to black or white
+    pub fn put(&mut self, x: usize, y: usize, black: bool) {
+        let idx = y * self.dy + x / WORD_BITS;
+        if black {
+            self.words[idx] |= bit(x);
+        } else {
+            self.words[idx] &= !bit(x);
+        }
+    }
+
+    /// Swaps black and white
+    pub fn invert(&mut self) {
+        for word in self.words.iter_mut() {
+            *word = !*word;
+        }
+        self.clear_padding();
+    }
+
+    /// Clears bits past the width of every scanline
+    fn clear_padding(&mut self) {
+        let used = self.width % WORD_BITS;
+        if used == 0 || self.dy == 0 {
+            return;
+        }
+        let mask = !0u64 << (WORD_BITS - used);
+        for y in 0..self.height {
+            self.words[y * self.dy + self.dy - 1] &= mask;
+        }
+    }
+
+    /// Clears every pixel that is set in a one byte per pixel mask
+    pub fn clear_mask(&mut self, mask: &[u8]) {
+        for (idx, masked) in mask.iter().enumerate() {
+            if *masked == 1 {
+                self.put(idx % self.width, idx / self.width, false);
+            }
+        }
+    }
+
+    /// Returns number of black pixels
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Labels connected components of black pixels and measures each of them
+    pub fn connected_components(&self, connectivity: &Connectivity) -> Components {
+        label_components(self, connectivity)
+    }
+
+    /// Guesses whether black pixels are the foreground, assuming border pixels are mostly
+    /// background. When the border is inconclusive, the minority color is the foreground.
+    pub fn detect_black_on_white(&self) -> bool {
+        let (w, h) = (self.width, self.height);
+        let mut black = 0;
+        let mut total = 0;
+        for y in 0..h {
+            for x in 0..w {
+                if y == 0 || x == 0 || y == h - 1 || x == w - 1 {
+                    total += 1;
+                    if self.at(x, y) {
+                        black += 1;
+                    }
+                }
+            }
+        }
+        let ratio = black as f64 / total.max(1) as f64;
+        if ratio <= 0.4 {
+            return true;
+        }
+        if ratio >= 0.6 {
+            return false;
+        }
+        return self.count() * 2 <= w * h;
+    }
+
+    /// finds next black pixel of the image, starting at the given point and scanning row by
+    /// row. Skips whole words of white pixels at once.
+    pub fn find_next(&self, point: Point) -> Option<Box<Point>> {
+        let mut x0 = point.x.max(0f64) as usize;
+        for y in (point.y.max(0f64) as usize)..self.height {
+            let row = y * self.dy;
+            let mut w = x0 / WORD_BITS;
+            while w < self.dy {
+                let mut word = self.words[row + w];
+                if w == x0 / WORD_BITS {
+                    word &= !0u64 >> (x0 % WORD_BITS);
+                }
+                if word != 0 {
+                    let x = w * WORD_BITS + word.leading_zeros() as usize;
+                    return Some(Box::new(Point::new(x as f64, y as f64)));
+                }
+                w += 1;
+            }
+            x0 = 0;
+        }
+        return None;
+    }
+
+    /// compute a path in the given pixmap, separating black from white.
+    ///
+    /// Start path at the point (x0,x1), which must be an upper left corner
+    /// of the path. Also compute the area enclosed by the path. Sign is
+    /// required for correct interpretation of turnpolicies, "+" for an
+    /// outline of black pixels and "-" for a hole.
+    pub fn find_path(
+        &self,
+        point: Point,
+        sign: &str,
+        turn_policy: SupportedTurnpolicyValues,
+    ) -> Path {
+        let mut path: Path = Path::default();
+        let mut x = point.x;
+        let mut y = point.y;
+        let mut dirx = 0f64;
+        let mut diry = 1f64;
+        path.sign = sign.to_string();
+
+        loop {
+            /* add point to path */
+            path.pt.push(Point::new(x, y));
+            path.max_x = path.max_x.max(x);
+            path.min_x = path.min_x.min(x);
+            path.max_y = path.max_y.max(y);
+            path.min_y = path.min_y.min(y);
+            path.len += 1;
+
+            /* move to next point */
+            x += dirx;
+            y += diry;
+            path.area -= x * diry;
+
+            if x == point.x && y == point.y {
+                break;
+            }
+
+            /* determine next direction */
+            let left = self.get(
+                x + (dirx + diry - 1f64) / 2f64,
+                y + (diry - dirx - 1f64) / 2f64,
+            );
+            let right = self.get(
+                x + (dirx - diry - 1f64) / 2f64,
+                y + (diry + dirx - 1f64) / 2f64,
+            );
+
+            let turn_right = if right && !left {
+                /* ambiguous turn */
+                turn_policy == SupportedTurnpolicyValues::Right
+                    || (turn_policy == SupportedTurnpolicyValues::Black && path.sign == "+")
+                    || (turn_policy == SupportedTurnpolicyValues::White && path.sign == "-")
+                    || (turn_policy == SupportedTurnpolicyValues::Majority && self.majority(x, y))
+                    || (turn_policy == SupportedTurnpolicyValues::Minority && !self.majority(x, y))
+            } else {
+                right
+            };
+
+            if turn_right {
+                let tmp = dirx;
+                dirx = -diry;
+                diry = tmp;
+            } else if !left {
+                /* left turn */
+                let tmp = dirx;
+                dirx = diry;
+                diry = -tmp;
+            }
+        } /* while this path */
+        return path;
+    }
+
+    /// return the "majority" value of bitmap bm at intersection (x,y). We
+    /// assume that the bitmap is balanced at "radius" 1.
+    pub fn majority(&self, x: f64, y: f64) -> bool {
+        let vote = |black: bool| if black { 1 } else { -1 };
+        for i in 2..5 {
+            let i = i as f64;
+            let mut ct = 0;
+            let mut a = -i + 1f64;
+            while a <= i - 1f64 {
+                ct += vote(self.get(x + a, y + i - 1f64));
+                ct += vote(self.get(x + i - 1f64, y + a - 1f64));
+                ct += vote(self.get(x + a - 1f64, y - i));
+                ct += vote(self.get(x - i, y + a));
+                a += 1f64;
+            }
+            if ct > 0 {
+                return true;
+            }
+            if ct < 0 {
+                return false;
+            }
+        }
+        return false;
+    }
+
+    /// xor the given pixmap with the interior of the given path. Note: the
+    /// path must be within the dimensions of the pixmap.
+    pub fn xor_path(&mut self, path: &Path) {
+        let mut y1 = path.pt[0].y;
+        for i in 1..path.len {
+            let x = path.pt[i].x;
+            let y = path.pt[i].y;
+            if y != y1 {
+                let min_y = if y1 < y { y1 } else { y };
+                self.xor_span(min_y as usize, x as usize, path.max_x as usize);
+                y1 = y;
+            }
+        }
+    }
+
+    /// flip pixels x0..x1 of scanline y, a word at a time
+    fn xor_span(&mut self, y: usize, x0: usize, x1: usize) {
+        if x0 >= x1 {
+            return;
+        }
+        let row = y * self.dy;
+        let (first, last) = (x0 / WORD_BITS, (x1 - 1) / WORD_BITS);
+        for w in first..=last {
+            let mut mask = !0u64;
+            if w == first {
+                mask &= !0u64 >> (x0 % WORD_BITS);
+            }
+            if w == last {
+                mask &= !0u64 << (WORD_BITS - ((x1 - 1) % WORD_BITS + 1));
+            }
+            self.words[row + w] ^= mask;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_raw_scanlines_not_a_multiple_of_8_wide() {
+        // 10 pixels wide, the last 6 bits of every second byte being padding
+        let mut bytes = b"P4\n10 2\n".to_vec();
+        bytes.extend_from_slice(&[0b1000_0000, 0b0111_1111, 0b0000_0000, 0b1111_1111]);
+        let bm = BinaryBitmap::from_pbm(&bytes).unwrap();
+        assert_eq!(
+            bm.to_data(),
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1]
+        );
+        assert_eq!(bm.count(), 4);
+    }
+
+    #[test]
+    fn rejects_headers_larger_than_the_data() {
+        assert!(BinaryBitmap::from_pbm(b"P4\n100000000 100000000\n\0").is_none());
+        assert!(BinaryBitmap::from_pbm(b"P1\n18446744073709551615 2\n0").is_none());
+        assert!(BinaryBitmap::from_pbm(b"P1\n2 2\n0 1 1").is_none());
+        assert!(BinaryBitmap::from_pbm(b"P1\n2 2\n0 1 1 0").is_some());
+    }
+
+    #[test]
+    fn finds_pixels_past_a_word_boundary() {
+        let mut bm = BinaryBitmap::new(130, 2);
+        bm.put(70, 0, true);
+        bm.put(129, 1, true);
+        let first = bm.find_next(Point::new(0f64, 0f64)).unwrap();
+        assert_eq!((first.x, first.y), (70f64, 0f64));
+        let next = bm.find_next(Point::new(71f64, 0f64)).unwrap();
+        assert_eq!((next.x, next.y), (129f64, 1f64));
+        assert!(bm.find_next(Point::new(130f64, 1f64)).is_none());
+    }
+
+    #[test]
+    fn flips_spans_across_word_boundaries() {
+        let mut bm = BinaryBitmap::new(140, 1);
+        bm.xor_span(0, 60, 130);
+        let data = bm.to_data();
+        assert!((0..140).all(|x| (data[x] == 1) == (x >= 60 && x < 130)));
+        bm.xor_span(0, 64, 128);
+        assert_eq!(bm.count(), 6);
+        assert_eq!(bm.words[2] & !(!0u64 << 52), 0);
+    }
+}
@@ -1,18 +1,21 @@
-use super::path::Path;
+use super::binary_bitmap::BinaryBitmap;
 use super::point::Point;
-use constants::SupportedTurnpolicyValues;
+use constants::{Channels, ColorMetrics, Connectivity};
 use image::{DynamicImage, ImageBuffer, Rgba};
 use types::histogram::Histogram;
+use utils::{channel_value, color_distance};
 
 /// Represents a bitmap where each pixel can be a number in range of 0..255
 ///
-/// Used internally to store luminance data.
+/// Used internally to store luminance data, or the levels of whichever channel was selected.
 #[derive(Clone)]
 pub struct Bitmap {
     pub histogram: Histogram,
     pub width: usize,
     pub height: usize,
     pub size: usize,
+    pub channel: Channels,
+    pub matte: [u8; 3],
     pub data: Vec<u8>,
     pub flat: Vec<u8>,
     pub raw: ImageBuffer<Rgba<u8>, Vec<u8>>,
@@ -20,31 +23,91 @@ pub struct Bitmap {
 
 impl Bitmap {
     pub fn new(image: DynamicImage) -> Bitmap {
+        Bitmap::with_channel(image, Channels::Rec709, [255, 255, 255])
+    }
+
+    /// Creates a bitmap of the given dimensions without any pixel data, for input that is
+    /// traced from a {@link BinaryBitmap} directly
+    pub fn blank(width: usize, height: usize) -> Bitmap {
+        let raw = ImageBuffer::new(0, 0);
+        return Bitmap {
+            histogram: Histogram::new(raw.clone(), &[]),
+            width,
+            height,
+            size: width * height,
+            channel: Channels::Rec709,
+            matte: [255, 255, 255],
+            data: vec![],
+            flat: vec![],
+            raw,
+        };
+    }
+
+    /// Creates a bitmap reading pixel levels from the given channel, with semi-transparent
+    /// pixels composited over the matte color
+    pub fn with_channel(image: DynamicImage, channel: Channels, matte: [u8; 3]) -> Bitmap {
         let raw = image.to_rgba();
-        let mut flat = raw.clone();
         let width = raw.width() as usize;
         let height = raw.height() as usize;
-        for pixel in flat.pixels_mut() {
-            let opacity: i32 = (pixel[3] / 255) as i32;
-            let r = 255 + ((pixel[0] as i32 - 255) * opacity) as u8;
-            let g = 255 + ((pixel[1] as i32 - 255) * opacity) as u8;
-            let b = 255 + ((pixel[2] as i32 - 255) * opacity) as u8;
-            pixel[0] = r;
-            pixel[1] = g;
-            pixel[2] = b;
-        }
+        let flat = Bitmap::composite(&raw, matte);
+        let data = Bitmap::read_channel(&flat, &channel);
 
         return Bitmap {
-            histogram: Histogram::new(raw.clone()),
+            histogram: Histogram::new(raw.clone(), &data),
             width,
             height,
             size: (width * height) as usize,
-            data: raw.to_vec(),
-            flat: flat.to_vec(),
+            channel,
+            matte,
+            data,
+            flat,
             raw,
         };
     }
 
+    /// Blends every pixel over the matte color according to its opacity. The alpha
+    /// component is kept as is, so that it could still be read as a channel.
+    fn composite(raw: &[u8], matte: [u8; 3]) -> Vec<u8> {
+        let mut flat = raw.to_vec();
+        for pixel in flat.chunks_mut(4) {
+            let opacity = pixel[3] as u32;
+            for c in 0..3 {
+                pixel[c] = ((pixel[c] as u32 * opacity + matte[c] as u32 * (255 - opacity) + 127)
+                    / 255) as u8;
+            }
+        }
+        return flat;
+    }
+
+    /// Collects the level of every pixel in the given channel
+    fn read_channel(pixels: &[u8], channel: &Channels) -> Vec<u8> {
+        pixels
+            .chunks(4)
+            .map(|pixel| channel_value(pixel, channel))
+            .collect()
+    }
+
+    /// Changes the color semi-transparent pixels are composited over, recalculating data and histogram
+    pub fn set_matte(&mut self, matte: [u8; 3]) {
+        if matte == self.matte {
+            return;
+        }
+        self.flat = Bitmap::composite(&self.raw, matte);
+        self.data = Bitmap::read_channel(&self.flat, &self.channel);
+        self.histogram = Histogram::new(self.raw.clone(), &self.data);
+        self.matte = matte;
+    }
+
+    /// Switches the channel pixel levels are read from, recalculating data and histogram
+    pub fn set_channel(&mut self, channel: Channels) {
+        if channel == self.channel {
+            return;
+        }
+        self.data = Bitmap::read_channel(&self.flat, &channel);
+        self.histogram = Histogram::new(self.raw.clone(), &self.data);
+        self.channel = channel;
+    }
+
     /// Returns pixel value
     pub fn get_value_at(&self, x: f64, y: f64) -> Option<&u8> {
         let idx = self.point_to_index(x, y).unwrap();
@@ -80,219 +143,208 @@ impl Bitmap {
         };
     }
 
-    pub fn generate_binary_bitmap(&self, blackOnWhite: bool, threshold: u8) -> Bitmap {
-        let mut bm = self.clone();
-        bm.data = bm
-            .data
-            .iter()
-            .map(|pixel| {
-                let pastTheThreshold = if blackOnWhite {
-                    *pixel > threshold
-                } else {
-                    *pixel < threshold
-                };
-                return if pastTheThreshold { 0u8 } else { 1u8 };
-            })
-            .collect();
+    /// Generates a new thresholded bitmap, pixels past the threshold being white (0) and the rest
+    /// black (1)
+    pub fn generate_binary_bitmap(&self, blackOnWhite: bool, threshold: u8) -> BinaryBitmap {
+        let mut bm = BinaryBitmap::new(self.width, self.height);
+        for (idx, pixel) in self.data.iter().enumerate() {
+            let pastTheThreshold = if blackOnWhite {
+                *pixel > threshold
+            } else {
+                *pixel < threshold
+            };
+            if !pastTheThreshold {
+                bm.put(idx % self.width, idx / self.width, true);
+            }
+        }
         return bm;
     }
 
-    pub fn get_raw_pixels(&self) -> Vec<u8> {
-        self.data.clone()
-    }
-
-    /// finds next black pixel of the image
-    pub fn find_next(&self, point: Point) -> Option<Box<Point>> {
-        let mut i = self.point_to_index(point.x, point.y).unwrap() as usize;
-        while i < self.size && self.data[i] != 1 {
-            i += 1;
+    /// Generates a new binary bitmap where pixels within `tolerance` of the given color, measured
+    /// with the given metric, are black and every other pixel white
+    pub fn generate_color_bitmap(
+        &self,
+        color: [u8; 3],
+        tolerance: f64,
+        metric: &ColorMetrics,
+    ) -> BinaryBitmap {
+        let mut bm = BinaryBitmap::new(self.width, self.height);
+        for (idx, pixel) in self.flat.chunks(4).enumerate() {
+            if color_distance(pixel, color, metric) <= tolerance {
+                bm.put(idx % self.width, idx / self.width, true);
+            }
         }
-        return match i < self.size {
-            true => Some(Box::new(self.index_to_point(i))),
-            false => None,
-        };
+        return bm;
     }
 
-    /// compute a path in the given pixmap, separating black from white.
-    ///
-    /// Start path at the point (x0,x1), which must be an upper left corner
-    /// of the path. Also compute the area enclosed by the path. Return a
-    /// new path object, or NULL on error (note that a legitimate path
-    /// cannot have length 0). Sign is required for correct interpretation
-    /// of turnpolicies.
-    pub fn find_path(&mut self, point: Point, turn_policy: SupportedTurnpolicyValues) -> Path {
-        let mut path: Path = Path::default();
-        let mut x = point.x;
-        let mut y = point.y;
-        let mut dirx = 0f64;
-        let mut diry = 1f64;
+    /// Flood fills from the given pixel indexes across neighbouring pixels accepted by `matches`.
+    /// Returns a mask holding 1 for every reached pixel and 0 for the rest.
+    pub fn flood_fill<F>(&self, seeds: &[usize], connectivity: &Connectivity, matches: F) -> Vec<u8>
+    where
+        F: Fn(usize) -> bool,
+    {
+        let mut mask = vec![0u8; self.size];
+        let mut stack: Vec<usize> = seeds
+            .iter()
+            .cloned()
+            .filter(|idx| *idx < self.size)
+            .collect();
+        let offsets: &[(isize, isize)] = match connectivity {
+            Connectivity::Four => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+            Connectivity::Eight => &[
+                (1, 0),
+                (-1, 0),
+                (0, 1),
+                (0, -1),
+                (1, 1),
+                (-1, 1),
+                (1, -1),
+                (-1, -1),
+            ],
+        };
 
-        path.sign = match self.get_value_at(point.x, point.y) {
-            Some(val) => {
-                if *val > 0 {
-                    "+".to_string()
-                } else {
-                    "-".to_string()
+        while let Some(idx) = stack.pop() {
+            if mask[idx] == 1 || !matches(idx) {
+                continue;
+            }
+            mask[idx] = 1;
+            let x = (idx % self.width) as isize;
+            let y = (idx / self.width) as isize;
+            for (dx, dy) in offsets {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx >= 0 && ny >= 0 && (nx as usize) < self.width && (ny as usize) < self.height {
+                    let next = ny as usize * self.width + nx as usize;
+                    if mask[next] == 0 {
+                        stack.push(next);
+                    }
                 }
             }
-            None => "+".to_string(),
+        }
+        return mask;
+    }
+
+    /// Generates a new binary bitmap of the connected region around the seed pixel. Pixels join the
+    /// region when their level is within `tolerance` of the seed level, or, when a metric is given,
+    /// when their color is within `tolerance` of the seed color.
+    pub fn select_region(
+        &self,
+        x: usize,
+        y: usize,
+        tolerance: f64,
+        connectivity: &Connectivity,
+        metric: Option<&ColorMetrics>,
+    ) -> BinaryBitmap {
+        let seed = y * self.width + x;
+        // a blank bitmap has no levels or colors to select by
+        let empty = match metric {
+            Some(_) => self.flat.is_empty(),
+            None => self.data.is_empty(),
         };
-        let mut searching = true;
-        while searching {
-            /* add point to path */
-            path.pt.push(Point::new(x, y));
-            if x > path.max_x {
-                path.max_x = x;
-            }
-            if x < path.min_x {
-                path.min_x = x;
-            }
-            if y > path.max_y {
-                path.max_y = y;
-            }
-            if y < path.min_y {
-                path.min_y = y;
+        if x >= self.width || y >= self.height || empty {
+            return BinaryBitmap::new(self.width, self.height);
+        }
+        let mask = match metric {
+            Some(metric) => {
+                let p = &self.flat[seed * 4..seed * 4 + 4];
+                let color = [p[0], p[1], p[2]];
+                self.flood_fill(&[seed], connectivity, |idx| {
+                    color_distance(&self.flat[idx * 4..idx * 4 + 4], color, metric) <= tolerance
+                })
             }
-            path.len += 1;
-            /* move to next point */
-            x += dirx;
-            y += diry;
-            path.area -= x * diry;
-            if x == point.x && y == point.y {
-                searching = false;
+            None => {
+                let level = self.data[seed] as f64;
+                self.flood_fill(&[seed], connectivity, |idx| {
+                    (self.data[idx] as f64 - level).abs() <= tolerance
+                })
             }
+        };
+        return BinaryBitmap::from_data(&mask, self.width, self.height);
+    }
 
-            /* determine next direction */
-            let left = self
-                .get_value_at(
-                    x + (dirx + diry - 1f64) / 2f64,
-                    y + (diry - dirx - 1f64) / 2f64,
-                )
-                .is_some();
-            let right = self
-                .get_value_at(
-                    x + (dirx - diry - 1f64) / 2f64,
-                    y + (diry + dirx - 1f64) / 2f64,
-                )
-                .is_some();
-
-            if right && !left {
-                /* ambiguous turn */
-                if turn_policy == SupportedTurnpolicyValues::Right
-                    || (turn_policy == SupportedTurnpolicyValues::Black
-                        && path.sign == "+".to_string())
-                    || (turn_policy == SupportedTurnpolicyValues::White
-                        && path.sign == "-".to_string())
-                    || (turn_policy == SupportedTurnpolicyValues::Majority && self.majority(x, y))
-                    || (turn_policy == SupportedTurnpolicyValues::Minority && !self.majority(x, y))
-                {
-                    /* right turn */
-                    let tmp = dirx;
-                    dirx = -diry;
-                    diry = tmp;
-                } else {
-                    /* left turn */
-                    let tmp = dirx;
-                    dirx = diry;
-                    diry = -tmp;
-                }
-            } else if right {
-                /* right turn */
-                let tmp = dirx;
-                dirx = -diry;
-                diry = tmp;
-            } else if !left {
-                /* left turn */
-                let tmp = dirx;
-                dirx = diry;
-                diry = -tmp;
+    /// Returns indexes of the pixels along the edges of the image
+    pub fn border_indexes(&self) -> Vec<usize> {
+        let mut border = vec![];
+        if self.size == 0 {
+            return border;
+        }
+        for x in 0..self.width {
+            border.push(x);
+            if self.height > 1 {
+                border.push((self.height - 1) * self.width + x);
             }
-        } /* while this path */
-        return path;
+        }
+        for y in 1..(self.height.max(1) - 1) {
+            border.push(y * self.width);
+            if self.width > 1 {
+                border.push(y * self.width + self.width - 1);
+            }
+        }
+        return border;
     }
 
-    /// return the "majority" value of bitmap bm at intersection (x,y). We
-    /// assume that the bitmap is balanced at "radius" 1.
-    pub fn majority(&self, x: f64, y: f64) -> bool {
-        for i in 2..5 {
-            let mut ct = 0;
-            for a in (-i + 1)..=(i - 1) {
-                ct += if self
-                    .get_value_at(x + (a as f64), y + (i as f64) - 1f64)
-                    .is_some()
-                {
-                    1
-                } else {
-                    -1
-                };
-                ct += if self
-                    .get_value_at(x + (i as f64) - 1f64, y + (a as f64) - 1f64)
-                    .is_some()
-                {
-                    1
-                } else {
-                    -1
-                };
-                ct += if self
-                    .get_value_at(x + (a as f64) - 1f64, y - (i as f64))
-                    .is_some()
-                {
-                    1
-                } else {
-                    -1
-                };
-                ct += if self.get_value_at(x - (i as f64), y + (a as f64)).is_some() {
-                    1
-                } else {
-                    -1
-                };
+    /// Finds background pixels by flood filling from the border of the image across pixels within
+    /// `tolerance` of the border color, or of the border level when no metric is given. The border
+    /// color is the per component median of the border pixels. Returns a mask holding 1 for
+    /// background pixels.
+    pub fn find_background(
+        &self,
+        tolerance: f64,
+        connectivity: &Connectivity,
+        metric: Option<&ColorMetrics>,
+    ) -> Vec<u8> {
+        let border = self.border_indexes();
+        let median = |mut values: Vec<u8>| -> u8 {
+            values.sort();
+            if values.is_empty() {
+                0
+            } else {
+                values[values.len() / 2]
             }
-            if ct > 0 {
-                return true;
+        };
+        match metric {
+            Some(metric) => {
+                let mut color = [0u8; 3];
+                for c in 0..3 {
+                    color[c] = median(border.iter().map(|idx| self.flat[idx * 4 + c]).collect());
+                }
+                self.flood_fill(&border, connectivity, |idx| {
+                    color_distance(&self.flat[idx * 4..idx * 4 + 4], color, metric) <= tolerance
+                })
             }
-            if ct < 0 {
-                return false;
+            None => {
+                let level = median(border.iter().map(|idx| self.data[*idx]).collect()) as f64;
+                self.flood_fill(&border, connectivity, |idx| {
+                    (self.data[idx] as f64 - level).abs() <= tolerance
+                })
             }
         }
-        return false;
     }
 
-    /// xor the given pixmap with the interior of the given path. Note: the
-    /// path must be within the dimensions of the pixmap.
-    pub fn xor_path(&mut self, path: Path) {
-        let len = path.len;
-        let mut y1 = path.pt[0].y;
+    /// Guesses whether the image has a dark foreground on a light background. Border pixels are
+    /// assumed to be mostly background; when they are inconclusive, the side of the threshold
+    /// holding most pixels of the histogram is.
+    pub fn detect_black_on_white(&self, threshold: u8) -> bool {
+        let border = self.border_indexes();
+        let light = border
+            .iter()
+            .filter(|idx| self.data[**idx] > threshold)
+            .count() as f64;
+        let ratio = light / border.len() as f64;
+        if ratio >= 0.6 {
+            return true;
+        }
+        if ratio <= 0.4 {
+            return false;
+        }
 
-        for i in 1..len {
-            let x = path.pt[i].x;
-            let y = path.pt[i].y;
+        let light_pixels: usize = self.histogram.lum[(threshold as usize + 1)..]
+            .iter()
+            .map(|count| *count as usize)
+            .sum();
+        return light_pixels * 2 >= self.size;
+    }
 
-            if y != y1 {
-                let min_y = if y1 < y { y1 } else { y };
-                let max_x = path.max_x;
-                for j in (x as usize)..(max_x as usize) {
-                    let idx = self.point_to_index(j as f64, min_y);
-                    match idx {
-                        Some(val) => {
-                            self.data[val] = if self.get_value_at(j as f64, min_y).is_some() {
-                                0
-                            } else {
-                                1
-                            }
-                        }
-                        None => {
-                            if let Some(last) = self.data.last_mut() {
-                                if self.get_value_at(j as f64, min_y).is_some() {
-                                    *last = 0
-                                } else {
-                                    *last = 1
-                                }
-                            };
-                        }
-                    }
-                }
-                y1 = y
-            }
-        }
+    pub fn get_raw_pixels(&self) -> Vec<u8> {
+        self.data.clone()
     }
 }
@@ -0,0 +1,443 @@
+use super::curve::fixed;
+use super::fit::fit_polyline;
+use super::point::Point;
+use super::segment::Segment;
+use super::transform::Transform;
+use std::collections::HashSet;
+
+/// Offsets of the 8 neighbours of a pixel, clockwise starting north, as P2..P9 in Zhang and
+/// Suen's paper
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// A line of black pixels traced along its middle
+#[derive(Clone)]
+pub struct Stroke {
+    /// centers of the skeleton pixels the stroke runs through
+    pub points: Vec<Point>,
+    /// whether the last point connects back to the first
+    pub closed: bool,
+    /// cubic Bezier segments fitted to the points
+    pub segments: Vec<Segment>,
+    /// estimated width of the line
+    pub width: f64,
+}
+
+impl Stroke {
+    /// Returns the stroke with its points and segments mapped through the given transform, the
+    /// width being scaled by the average scale of the transform
+    pub fn transform(&self, transform: &Transform) -> Stroke {
+        let scale = (transform.a * transform.d - transform.b * transform.c)
+            .abs()
+            .sqrt();
+        Stroke {
+            points: self.points.iter().map(|p| transform.apply(*p)).collect(),
+            closed: self.closed,
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| segment.transform(transform))
+                .collect(),
+            width: self.width * scale,
+        }
+    }
+}
+
+/// Traces the middle of every line of set pixels in one byte per pixel data.
+///
+/// The pixels are thinned to a one pixel wide skeleton with the Zhang-Suen algorithm, branches
+/// shorter than `spur_length` pixels running from a junction to a free end are pruned, and the
+/// rest linked into polylines between ends and junctions, or into closed loops. Every polyline
+/// is fitted with Bezier curves within `max_error` pixels, and its width estimated from the
+/// distance of its skeleton pixels to the nearest unset pixel.
+pub fn trace_centerlines(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    spur_length: f64,
+    max_error: f64,
+) -> Vec<Stroke> {
+    let distances = distance_transform(data, width, height);
+    let mut skeleton = thin(data, width, height);
+    prune_spurs(&mut skeleton, width, height, spur_length);
+    return link(&skeleton, width, height)
+        .into_iter()
+        .map(|(pixels, closed)| {
+            let points: Vec<Point> = pixels
+                .iter()
+                .map(|idx| Point::new((idx % width) as f64 + 0.5, (idx / width) as f64 + 0.5))
+                .collect();
+            // a pixel at distance d from the background is the middle of a line 2d - 1 wide
+            let mean = pixels.iter().map(|idx| distances[*idx]).sum::<f64>() / pixels.len() as f64;
+            Stroke {
+                segments: fit_polyline(&points, closed, max_error),
+                points,
+                closed,
+                width: (2f64 * mean - 1f64).max(1f64),
+            }
+        })
+        .collect();
+}
+
+/// Returns the index of the neighbour of pixel idx at the given offset, if it is set
+fn neighbour(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    idx: usize,
+    offset: (isize, isize),
+) -> Option<usize> {
+    let (x, y) = (
+        (idx % width) as isize + offset.0,
+        (idx / width) as isize + offset.1,
+    );
+    if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+        return None;
+    }
+    let idx = y as usize * width + x as usize;
+    if data[idx] != 0 {
+        Some(idx)
+    } else {
+        None
+    }
+}
+
+/// Returns the indexes of the set neighbours of pixel idx. A diagonal neighbour is left out
+/// when a side neighbour next to both of them is set, so that the pixels around a junction do
+/// not all link to each other.
+fn neighbours(data: &[u8], width: usize, height: usize, idx: usize) -> Vec<usize> {
+    NEIGHBOURS
+        .iter()
+        .filter(|(dx, dy)| {
+            *dx == 0
+                || *dy == 0
+                || (neighbour(data, width, height, idx, (*dx, 0)).is_none()
+                    && neighbour(data, width, height, idx, (0, *dy)).is_none())
+        })
+        .filter_map(|offset| neighbour(data, width, height, idx, *offset))
+        .collect()
+}
+
+/// Thins set pixels to a skeleton one pixel wide with the Zhang-Suen algorithm, then removes
+/// the pixels in the inner corner of every staircase, which the algorithm leaves 4-connected
+pub fn thin(data: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut skeleton: Vec<u8> = data.iter().map(|value| (*value != 0) as u8).collect();
+    let ring = |skeleton: &[u8], idx: usize| -> [bool; 8] {
+        let mut ring = [false; 8];
+        for (k, offset) in NEIGHBOURS.iter().enumerate() {
+            ring[k] = neighbour(skeleton, width, height, idx, *offset).is_some();
+        }
+        ring
+    };
+    let transitions = |p: &[bool; 8]| (0..8).filter(|k| !p[*k] && p[(k + 1) % 8]).count();
+
+    loop {
+        let mut changed = false;
+        for step in 0..2 {
+            let removed: Vec<usize> = (0..skeleton.len())
+                .filter(|idx| {
+                    if skeleton[*idx] == 0 {
+                        return false;
+                    }
+                    let p = ring(&skeleton, *idx);
+                    let count = p.iter().filter(|set| **set).count();
+                    // p[0] is north, p[2] east, p[4] south and p[6] west
+                    let sides = if step == 0 {
+                        !(p[0] && p[2] && p[4]) && !(p[2] && p[4] && p[6])
+                    } else {
+                        !(p[0] && p[2] && p[6]) && !(p[0] && p[4] && p[6])
+                    };
+                    count >= 2 && count <= 6 && transitions(&p) == 1 && sides
+                })
+                .collect();
+            changed |= !removed.is_empty();
+            for idx in removed {
+                skeleton[idx] = 0;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    for idx in 0..skeleton.len() {
+        if skeleton[idx] == 0 {
+            continue;
+        }
+        let p = ring(&skeleton, idx);
+        // exactly two adjacent sides, as in the inner corner of a staircase, and not the
+        // middle of a crossing or a T
+        let corner = (0..4).any(|k| {
+            let side = |offset: usize| p[(2 * (k + offset)) % 8];
+            side(0) && side(1) && !side(2) && !side(3)
+        });
+        if corner && connected(&p) {
+            skeleton[idx] = 0;
+        }
+    }
+    return skeleton;
+}
+
+/// Returns whether the set neighbours of a pixel are 8-connected to each other without it.
+/// Neighbours next to each other around the ring are adjacent, and so are two sides with a
+/// corner in between.
+fn connected(ring: &[bool; 8]) -> bool {
+    let start = match (0..8).find(|k| ring[*k]) {
+        Some(start) => start,
+        None => return true,
+    };
+    let mut reached = [false; 8];
+    let mut stack = vec![start];
+    reached[start] = true;
+    while let Some(k) = stack.pop() {
+        let mut adjacent = vec![(k + 1) % 8, (k + 7) % 8];
+        if k % 2 == 0 {
+            adjacent.push((k + 2) % 8);
+            adjacent.push((k + 6) % 8);
+        }
+        for other in adjacent {
+            if ring[other] && !reached[other] {
+                reached[other] = true;
+                stack.push(other);
+            }
+        }
+    }
+    return (0..8).all(|k| !ring[k] || reached[k]);
+}
+
+/// Returns the distance from the center of every set pixel to the center of the nearest unset
+/// pixel, pixels outside of the image being unset, with a two pass chamfer transform
+pub fn distance_transform(data: &[u8], width: usize, height: usize) -> Vec<f64> {
+    let diagonal = 2f64.sqrt();
+    let mut distances: Vec<f64> = data
+        .iter()
+        .map(|value| if *value != 0 { f64::INFINITY } else { 0f64 })
+        .collect();
+    let at = |distances: &[f64], x: isize, y: isize| {
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            0f64
+        } else {
+            distances[y as usize * width + x as usize]
+        }
+    };
+    let forward = [
+        (-1, 0, 1f64),
+        (-1, -1, diagonal),
+        (0, -1, 1f64),
+        (1, -1, diagonal),
+    ];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let idx = y as usize * width + x as usize;
+            for (dx, dy, step) in forward.iter() {
+                let distance = at(&distances, x + dx, y + dy) + step;
+                distances[idx] = distances[idx].min(distance);
+            }
+        }
+    }
+    for y in (0..height as isize).rev() {
+        for x in (0..width as isize).rev() {
+            let idx = y as usize * width + x as usize;
+            for (dx, dy, step) in forward.iter() {
+                let distance = at(&distances, x - dx, y - dy) + step;
+                distances[idx] = distances[idx].min(distance);
+            }
+        }
+    }
+    return distances;
+}
+
+/// Removes branches of the skeleton that run from a free end to a junction in fewer than
+/// `length` pixels
+fn prune_spurs(skeleton: &mut [u8], width: usize, height: usize, length: f64) {
+    let ends: Vec<usize> = (0..skeleton.len())
+        .filter(|idx| skeleton[*idx] != 0 && neighbours(skeleton, width, height, *idx).len() == 1)
+        .collect();
+    for end in ends {
+        let mut branch = vec![end];
+        let mut junction = false;
+        loop {
+            let current = branch[branch.len() - 1];
+            let next: Vec<usize> = neighbours(skeleton, width, height, current)
+                .into_iter()
+                .filter(|idx| !branch.contains(idx))
+                .collect();
+            if next.len() != 1 {
+                // a free end, or a fork right at the current pixel
+                junction = next.len() > 1;
+                break;
+            }
+            if neighbours(skeleton, width, height, next[0]).len() > 2 {
+                junction = true;
+                break;
+            }
+            branch.push(next[0]);
+            if branch.len() as f64 >= length {
+                break;
+            }
+        }
+        if junction && (branch.len() as f64) < length {
+            for idx in branch {
+                skeleton[idx] = 0;
+            }
+        }
+    }
+}
+
+/// Links skeleton pixels into polylines running between ends and junctions, and closed loops
+/// of the pixels left over. Returns pixel indexes of every polyline, and whether it is closed.
+fn link(skeleton: &[u8], width: usize, height: usize) -> Vec<(Vec<usize>, bool)> {
+    let pixels: Vec<usize> = (0..skeleton.len())
+        .filter(|idx| skeleton[*idx] != 0)
+        .collect();
+    let degree = |idx: usize| neighbours(skeleton, width, height, idx).len();
+    let mut visited: HashSet<(usize, usize)> = HashSet::new();
+    let edge = |a: usize, b: usize| if a < b { (a, b) } else { (b, a) };
+    let mut lines = vec![];
+
+    // follows unvisited edges from the first pixel until reaching a pixel that is not part of
+    // a plain line, or running out of edges
+    let walk = |line: &mut Vec<usize>, visited: &mut HashSet<(usize, usize)>| loop {
+        let current = line[line.len() - 1];
+        if line.len() > 1 && degree(current) != 2 {
+            return;
+        }
+        let next = neighbours(skeleton, width, height, current)
+            .into_iter()
+            .find(|idx| !visited.contains(&edge(current, *idx)));
+        match next {
+            Some(next) => {
+                visited.insert(edge(current, next));
+                line.push(next);
+            }
+            None => return,
+        }
+    };
+
+    for idx in &pixels {
+        let idx = *idx;
+        match degree(idx) {
+            0 => lines.push((vec![idx], false)),
+            2 => {}
+            _ => {
+                for next in neighbours(skeleton, width, height, idx) {
+                    if visited.contains(&edge(idx, next)) {
+                        continue;
+                    }
+                    visited.insert(edge(idx, next));
+                    let mut line = vec![idx, next];
+                    walk(&mut line, &mut visited);
+                    lines.push((line, false));
+                }
+            }
+        }
+    }
+    // whatever is left are loops without any junction
+    for idx in &pixels {
+        let idx = *idx;
+        let unvisited = neighbours(skeleton, width, height, idx)
+            .into_iter()
+            .any(|next| !visited.contains(&edge(idx, next)));
+        if degree(idx) == 2 && unvisited {
+            let mut line = vec![idx];
+            walk(&mut line, &mut visited);
+            lines.push((line, false));
+        }
+    }
+
+    for (line, closed) in lines.iter_mut() {
+        if line.len() > 2 && line[0] == line[line.len() - 1] {
+            line.pop();
+            *closed = true;
+        }
+    }
+    return lines;
+}
+
+/// Renders a stroke as SVG path data, with every point mapped through the given transform
+pub fn render_stroke(stroke: &Stroke, transform: &Transform) -> String {
+    let origin = transform.apply(stroke.points[0]);
+    let mut path = format!("M {} {}", fixed(origin.x), fixed(origin.y));
+    if stroke.segments.is_empty() {
+        // a zero length line, drawn as a dot by round caps
+        path.push_str(&format!(" L {} {}", fixed(origin.x), fixed(origin.y)));
+    }
+    for segment in &stroke.segments {
+        match segment.transform(transform) {
+            Segment::Line { to, .. } => {
+                path.push_str(&format!(" L {} {}", fixed(to.x), fixed(to.y)))
+            }
+            Segment::Cubic { c1, c2, to, .. } => path.push_str(&format!(
+                " C {} {}, {} {}, {} {}",
+                fixed(c1.x),
+                fixed(c1.y),
+                fixed(c2.x),
+                fixed(c2.y),
+                fixed(to.x),
+                fixed(to.y)
+            )),
+        }
+    }
+    if stroke.closed {
+        path.push_str(" Z");
+    }
+    return path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws one pixel wide lines between the given pixels of a 15x15 image
+    fn draw(lines: &[((usize, usize), (usize, usize))]) -> Vec<u8> {
+        let mut data = vec![0u8; 15 * 15];
+        for ((x0, y0), (x1, y1)) in lines {
+            for y in *y0..=*y1 {
+                for x in *x0..=*x1 {
+                    data[y * 15 + x] = 1;
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn keeps_the_junction_of_a_cross() {
+        let data = draw(&[((2, 7), (12, 7)), ((7, 2), (7, 12))]);
+        assert_eq!(thin(&data, 15, 15), data);
+        let strokes = trace_centerlines(&data, 15, 15, 0f64, 0.5);
+        assert_eq!(strokes.len(), 4);
+        for stroke in &strokes {
+            assert!(!stroke.closed);
+            assert_eq!(stroke.points.len(), 6);
+        }
+    }
+
+    #[test]
+    fn keeps_the_junction_of_a_t() {
+        let data = draw(&[((2, 2), (12, 2)), ((7, 2), (7, 12))]);
+        assert_eq!(thin(&data, 15, 15), data);
+        let strokes = trace_centerlines(&data, 15, 15, 0f64, 0.5);
+        assert_eq!(strokes.len(), 3);
+        assert!(strokes.iter().all(|stroke| !stroke.closed));
+    }
+
+    #[test]
+    fn removes_the_inner_corners_of_a_staircase() {
+        let data = draw(&[((2, 2), (5, 2)), ((5, 2), (5, 5)), ((5, 5), (8, 5))]);
+        let skeleton = thin(&data, 15, 15);
+        // the corners at (5, 2) and (5, 5) leave 8-connected diagonal steps
+        assert_eq!(skeleton[2 * 15 + 5], 0);
+        assert_eq!(skeleton[5 * 15 + 5], 0);
+        let strokes = trace_centerlines(&data, 15, 15, 0f64, 0.5);
+        assert_eq!(strokes.len(), 1);
+    }
+}
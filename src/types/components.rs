@@ -0,0 +1,269 @@
+use super::binary_bitmap::BinaryBitmap;
+use super::point::Point;
+use constants::Connectivity;
+
+/// Measurements of a single connected component
+#[derive(Clone)]
+pub struct ComponentStats {
+    /// label of the component in the label image, starting at 1
+    pub label: u32,
+    /// number of pixels
+    pub area: usize,
+    pub min_x: usize,
+    pub min_y: usize,
+    /// inclusive right edge of the bounding box
+    pub max_x: usize,
+    /// inclusive bottom edge of the bounding box
+    pub max_y: usize,
+    /// mean of pixel centers
+    pub centroid: Point,
+    /// number of pixel edges between the component and the rest of the image
+    pub perimeter: usize,
+    /// number of components (1) minus number of holes
+    pub euler_number: i64,
+}
+
+/// Result of connected component labeling of a binary bitmap
+#[derive(Clone)]
+pub struct Components {
+    pub width: usize,
+    pub height: usize,
+    /// label of every pixel, 0 for white pixels
+    pub labels: Vec<u32>,
+    /// statistics of every component, stats[i] belonging to label i + 1
+    pub stats: Vec<ComponentStats>,
+}
+
+impl Components {
+    /// Returns label of the pixel at (x, y), 0 for white pixels and pixels outside of the image
+    pub fn label_at(&self, x: usize, y: usize) -> u32 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        return self.labels[y * self.width + x];
+    }
+}
+
+/// find root of a label in the union-find forest, compressing the path on the way
+fn find(parent: &mut Vec<u32>, label: u32) -> u32 {
+    let mut root = label;
+    while parent[root as usize] != root {
+        root = parent[root as usize];
+    }
+    let mut current = label;
+    while parent[current as usize] != root {
+        let next = parent[current as usize];
+        parent[current as usize] = root;
+        current = next;
+    }
+    return root;
+}
+
+fn union(parent: &mut Vec<u32>, a: u32, b: u32) {
+    let (ra, rb) = (find(parent, a), find(parent, b));
+    if ra < rb {
+        parent[rb as usize] = ra;
+    } else if rb < ra {
+        parent[ra as usize] = rb;
+    }
+}
+
+/// Labels the connected components of black pixels using two pass union-find labeling, and
+/// measures each of them.
+///
+/// The Euler number is computed by counting 2x2 pixel quads (Gray's bit quads), with holes being
+/// connected with the complementary connectivity.
+pub fn label_components(bm: &BinaryBitmap, connectivity: &Connectivity) -> Components {
+    let (width, height) = (bm.width, bm.height);
+    let mut labels = vec![0u32; width * height];
+    let mut parent: Vec<u32> = vec![0];
+    let black = |x: isize, y: isize| bm.get(x as f64, y as f64);
+
+    // first pass, provisional labels
+    for y in 0..height {
+        for x in 0..width {
+            if !bm.get(x as f64, y as f64) {
+                continue;
+            }
+            let (xi, yi) = (x as isize, y as isize);
+            let mut neighbours: Vec<(isize, isize)> = vec![(xi - 1, yi), (xi, yi - 1)];
+            if *connectivity == Connectivity::Eight {
+                neighbours.push((xi - 1, yi - 1));
+                neighbours.push((xi + 1, yi - 1));
+            }
+            let mut label = 0u32;
+            for (nx, ny) in neighbours {
+                if !black(nx, ny) {
+                    continue;
+                }
+                let other = labels[ny as usize * width + nx as usize];
+                if label == 0 {
+                    label = other;
+                } else {
+                    union(&mut parent, label, other);
+                }
+            }
+            if label == 0 {
+                label = parent.len() as u32;
+                parent.push(label);
+            }
+            labels[y * width + x] = label;
+        }
+    }
+
+    // second pass, resolve labels to consecutive numbers
+    let mut consecutive = vec![0u32; parent.len()];
+    let mut stats: Vec<ComponentStats> = vec![];
+    let mut sums: Vec<(f64, f64)> = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let idx = y * width + x;
+            if labels[idx] == 0 {
+                continue;
+            }
+            let root = find(&mut parent, labels[idx]) as usize;
+            if consecutive[root] == 0 {
+                stats.push(ComponentStats {
+                    label: stats.len() as u32 + 1,
+                    area: 0,
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                    centroid: Point::default(),
+                    perimeter: 0,
+                    euler_number: 0,
+                });
+                sums.push((0f64, 0f64));
+                consecutive[root] = stats.len() as u32;
+            }
+            let label = consecutive[root];
+            labels[idx] = label;
+
+            let component = &mut stats[label as usize - 1];
+            component.area += 1;
+            component.min_x = component.min_x.min(x);
+            component.max_x = component.max_x.max(x);
+            component.max_y = y;
+            sums[label as usize - 1].0 += x as f64 + 0.5;
+            sums[label as usize - 1].1 += y as f64 + 0.5;
+        }
+    }
+
+    let label_at = |x: isize, y: isize| -> u32 {
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            0
+        } else {
+            labels[y as usize * width + x as usize]
+        }
+    };
+
+    // perimeter, counting edges shared with pixels of other labels or the outside
+    let mut perimeters = vec![0usize; stats.len()];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let label = label_at(x, y);
+            if label == 0 {
+                continue;
+            }
+            for (dx, dy) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                if label_at(x + dx, y + dy) != label {
+                    perimeters[label as usize - 1] += 1;
+                }
+            }
+        }
+    }
+
+    // bit quads, per label present in each 2x2 window
+    let mut quads = vec![[0i64; 3]; stats.len()];
+    for y in -1..height as isize {
+        for x in -1..width as isize {
+            let window = [
+                label_at(x, y),
+                label_at(x + 1, y),
+                label_at(x, y + 1),
+                label_at(x + 1, y + 1),
+            ];
+            let mut seen: Vec<u32> = vec![];
+            for label in window.iter() {
+                if *label == 0 || seen.contains(label) {
+                    continue;
+                }
+                seen.push(*label);
+                let members: Vec<bool> = window.iter().map(|l| l == label).collect();
+                let count = members.iter().filter(|m| **m).count();
+                let q = &mut quads[*label as usize - 1];
+                match count {
+                    1 => q[0] += 1,
+                    3 => q[1] += 1,
+                    2 if members[0] == members[3] => q[2] += 1,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for (i, component) in stats.iter_mut().enumerate() {
+        let area = component.area as f64;
+        component.centroid = Point::new(sums[i].0 / area, sums[i].1 / area);
+        component.perimeter = perimeters[i];
+        let [q1, q3, qd] = quads[i];
+        component.euler_number = match connectivity {
+            Connectivity::Four => (q1 - q3 + 2 * qd) / 4,
+            Connectivity::Eight => (q1 - q3 - 2 * qd) / 4,
+        };
+    }
+
+    return Components {
+        width,
+        height,
+        labels,
+        stats,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a 6x6 square with a 2x2 hole in the middle, at (1, 1) of an 8x8 bitmap
+    fn annulus() -> BinaryBitmap {
+        let mut data = vec![0u8; 64];
+        for y in 1..7 {
+            for x in 1..7 {
+                let hole = x >= 3 && x < 5 && y >= 3 && y < 5;
+                data[y * 8 + x] = (!hole) as u8;
+            }
+        }
+        BinaryBitmap::from_data(&data, 8, 8)
+    }
+
+    #[test]
+    fn an_annulus_has_euler_number_0() {
+        for connectivity in vec![Connectivity::Four, Connectivity::Eight] {
+            let components = label_components(&annulus(), &connectivity);
+            assert_eq!(components.stats.len(), 1);
+            let stats = &components.stats[0];
+            assert_eq!(stats.euler_number, 0);
+            assert_eq!(stats.area, 32);
+            assert_eq!(stats.perimeter, 24 + 8);
+            assert_eq!(
+                (stats.min_x, stats.min_y, stats.max_x, stats.max_y),
+                (1, 1, 6, 6)
+            );
+            assert_eq!(components.label_at(3, 3), 0);
+            assert_eq!(components.label_at(1, 1), stats.label);
+        }
+    }
+
+    #[test]
+    fn connects_diagonal_pixels_by_connectivity() {
+        let bm = BinaryBitmap::from_data(&[1, 0, 0, 1], 2, 2);
+        let four = label_components(&bm, &Connectivity::Four);
+        assert_eq!(four.stats.len(), 2);
+        assert!(four.stats.iter().all(|stats| stats.euler_number == 1));
+        let eight = label_components(&bm, &Connectivity::Eight);
+        assert_eq!(eight.stats.len(), 1);
+        assert_eq!(eight.stats[0].euler_number, 1);
+    }
+}
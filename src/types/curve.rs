@@ -3,7 +3,10 @@ use super::point::{
     area_of_parallelogram, cubic_cross_product, cubic_inner_product, ddenom, distance_between,
     interval, quadratic_inner_product, Point,
 };
+use super::segment::{straighten, ArcSegment, QuadraticSegment, Segment};
+use super::transform::Transform;
 use crate::utils::{modulo, sign};
+use constants::CurveCommands;
 
 /// Casts a decimal number to a fixed length and returns it as
 /// a string. Truncates trailing 0's.
@@ -113,16 +116,195 @@ impl Curve {
         return self;
     }
 
+    /// Returns the curve with its control points and vertices mapped through the given
+    /// transform. Affine transforms map Bezier curves exactly, so the segments of the result
+    /// are the transformed segments of this curve.
+    pub fn transform(&self, transform: &Transform) -> Curve {
+        let mut curve = self.clone();
+        curve.c = self.c.iter().map(|p| transform.apply(*p)).collect();
+        curve.vertex = self.vertex.iter().map(|p| transform.apply(*p)).collect();
+        return curve;
+    }
+
+    /// Splits the curve into its drawn segments, a CORNER giving two lines through its
+    /// vertex and a CURVE one cubic Bezier segment
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut segments = Vec::with_capacity(self.n * 2);
+        if self.n == 0 {
+            return segments;
+        }
+        let mut from = self.c[(self.n - 1) * 3 + 2];
+        for i in 0..self.n {
+            let to = self.c[i * 3 + 2];
+            match self.tag[i] {
+                Tag::CORNER => {
+                    let corner = self.c[i * 3 + 1];
+                    segments.push(Segment::Line { from, to: corner });
+                    segments.push(Segment::Line { from: corner, to });
+                }
+                Tag::CURVE => segments.push(Segment::Cubic {
+                    from,
+                    c1: self.c[i * 3],
+                    c2: self.c[i * 3 + 1],
+                    to,
+                }),
+            }
+            from = to;
+        }
+        return segments;
+    }
+
+    /// Returns the exact bounding box of the curve as its top left and bottom right corners
+    pub fn bounds(&self) -> (Point, Point) {
+        let mut min = Point::new(f64::INFINITY, f64::INFINITY);
+        let mut max = Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for segment in self.segments() {
+            let (lo, hi) = segment.bounds();
+            min = Point::new(min.x.min(lo.x), min.y.min(lo.y));
+            max = Point::new(max.x.max(hi.x), max.y.max(hi.y));
+        }
+        return (min, max);
+    }
+
+    /// Returns the area enclosed by the curve, positive when it runs clockwise on screen (y
+    /// pointing down) and negative otherwise
+    pub fn signed_area(&self) -> f64 {
+        self.segments().iter().map(Segment::area).sum()
+    }
+
+    /// Returns the arc length of the curve
+    pub fn length(&self) -> f64 {
+        self.segments().iter().map(Segment::length).sum()
+    }
+
+    /// Returns the point at the given arc length from the start of the curve, wrapping around
+    /// for lengths past the total
+    pub fn point_at_length(&self, length: f64) -> Point {
+        let segments = self.segments();
+        let total = self.length();
+        if segments.is_empty() {
+            return Point::default();
+        }
+        let mut remaining = if total > 0f64 {
+            length.rem_euclid(total)
+        } else {
+            0f64
+        };
+        for segment in &segments {
+            let l = segment.length();
+            if remaining <= l {
+                return segment.point_at(segment.parameter_at_length(remaining));
+            }
+            remaining -= l;
+        }
+        return segments[segments.len() - 1].end();
+    }
+
+    /// Returns the point of the curve nearest to `p`, and its distance
+    pub fn nearest_point(&self, p: Point) -> (Point, f64) {
+        let mut best = (Point::default(), f64::INFINITY);
+        for segment in self.segments() {
+            let (min, max) = segment.bounds();
+            // skip segments whose bounding box is farther away than the best point so far
+            let dx = (min.x - p.x).max(p.x - max.x).max(0f64);
+            let dy = (min.y - p.y).max(p.y - max.y).max(0f64);
+            if (dx * dx + dy * dy).sqrt() > best.1 {
+                continue;
+            }
+            let (t, d) = segment.nearest(p);
+            if d < best.1 {
+                best = (segment.point_at(t), d);
+            }
+        }
+        return best;
+    }
+
+    /// Counts the crossings of the curve with the ray going from (x, y) in the positive x
+    /// direction. An odd count means the point is inside.
+    pub fn crossings(&self, x: f64, y: f64) -> usize {
+        self.segments()
+            .iter()
+            .map(|segment| segment.crossings(x, y))
+            .sum()
+    }
+
+    /// Returns a closed polygon approximating the curve, no farther than `tolerance` from it.
+    /// The last point connects back to the first, which is not repeated.
+    pub fn flatten(&self, tolerance: f64) -> Vec<Point> {
+        let segments = self.segments();
+        let mut points = vec![];
+        if segments.is_empty() {
+            return points;
+        }
+        points.push(segments[0].start());
+        for segment in &segments {
+            segment.flatten(tolerance, &mut points);
+        }
+        points.pop();
+        return points;
+    }
+
+    /// Converts the curve to lines and quadratic Bezier segments, no farther than `tolerance`
+    /// from it
+    pub fn to_quadratics(&self, tolerance: f64) -> Vec<QuadraticSegment> {
+        self.segments()
+            .iter()
+            .flat_map(|segment| segment.to_quadratics(tolerance))
+            .collect()
+    }
+
+    /// Converts the curve to lines and circular arcs, no farther than `tolerance` from it
+    pub fn to_arcs(&self, tolerance: f64) -> Vec<ArcSegment> {
+        self.segments()
+            .iter()
+            .flat_map(|segment| segment.to_arcs(tolerance))
+            .collect()
+    }
+
+    /// Reverses the direction of the curve. Besides the vertices, the segments are reversed
+    /// once they have been computed by {@link Curve::smooth}, each segment ending where it used
+    /// to start, with its control points swapped.
     pub fn reverse(&mut self) {
         let n = self.n;
-        let vertex = self.vertex.clone();
-        let mut y = n - 1;
-        for x in 0..(y - 1) {
-            self.vertex[x] = vertex[y];
-            self.vertex[y] = vertex[x];
-            y -= 1;
+        self.vertex.reverse();
+        if self.c.len() < n * 3 || self.tag.len() < n {
+            return;
+        }
+        // segment k of the reversed curve is segment n - 1 - k, running from its end point to
+        // the end point of the segment before it
+        let c = self.c.clone();
+        let beta = self.beta.clone();
+        for k in 0..n {
+            let i = n - 1 - k;
+            self.c[k * 3] = c[i * 3 + 1];
+            self.c[k * 3 + 1] = match self.tag[i] {
+                Tag::CURVE => c[i * 3],
+                Tag::CORNER => c[i * 3 + 1],
+            };
+            self.c[k * 3 + 2] = c[modulo(i + n - 1, n) * 3 + 2];
+            // the end point moves with its segment, and so does its position along the edge,
+            // measured from the other vertex
+            if beta.len() == n {
+                self.beta[k] = 1f64 - beta[modulo(i + n - 1, n)];
+            }
+        }
+        self.tag.reverse();
+        self.alpha.reverse();
+        self.alpha0.reverse();
+    }
+
+    /// Returns whether the curve runs clockwise on screen, y pointing down
+    pub fn is_clockwise(&self) -> bool {
+        self.signed_area() > 0f64
+    }
+
+    /// Reverses the curve unless it already runs in the given direction
+    pub fn orient(&mut self, clockwise: bool) {
+        if self.is_clockwise() != clockwise {
+            self.reverse();
         }
     }
+
     pub fn smooth(&mut self, alpha_max: f64) {
         let mut alpha = self.alpha.clone();
         let mut alpha0 = self.alpha0.clone();
@@ -404,35 +586,36 @@ impl Curve {
 
         return 0f64;
     }
-    pub fn render_curve(&self, width: f64, height: f64) -> String {
-        let origin = self.c[(self.n - 1) * 3 + 2];
+    /// Renders the curve as SVG path data, with every point mapped through `transform`
+    pub fn render_curve(&self, transform: &Transform) -> String {
+        let origin = transform.apply(self.c[(self.n - 1) * 3 + 2]);
         let mut i = 0;
         return self.tag.iter().fold(
             format!(
                 "M {o_x} {o_y}",
-                o_x = fixed(origin.x * width),
-                o_y = fixed(origin.y * height)
+                o_x = fixed(origin.x),
+                o_y = fixed(origin.y)
             ),
             |path, tag| {
-                let p0 = &self.c[i * 3].clone();
-                let p1 = &self.c[i * 3 + 1].clone();
-                let p2 = &self.c[i * 3 + 2].clone();
+                let p0 = transform.apply(self.c[i * 3]);
+                let p1 = transform.apply(self.c[i * 3 + 1]);
+                let p2 = transform.apply(self.c[i * 3 + 2]);
                 let res = match tag {
                     Tag::CURVE => format!(
                         " C {p0_x} {p0_y}, {p1_x} {p1_y}, {p2_x} {p2_y}",
-                        p0_x = fixed(p0.x * width),
-                        p0_y = fixed(p0.y * height),
-                        p1_x = fixed(p1.x * width),
-                        p1_y = fixed(p1.y * height),
-                        p2_x = fixed(p2.x * width),
-                        p2_y = fixed(p2.y * height)
+                        p0_x = fixed(p0.x),
+                        p0_y = fixed(p0.y),
+                        p1_x = fixed(p1.x),
+                        p1_y = fixed(p1.y),
+                        p2_x = fixed(p2.x),
+                        p2_y = fixed(p2.y)
                     ),
                     Tag::CORNER => format!(
-                        " L ${p1_x} ${p1_y} ${p2_x} ${p2_y}",
-                        p1_x = fixed(p1.x * width),
-                        p1_y = fixed(p1.y * height),
-                        p2_x = fixed(p2.x * width),
-                        p2_y = fixed(p2.y * height)
+                        " L {p1_x} {p1_y} {p2_x} {p2_y}",
+                        p1_x = fixed(p1.x),
+                        p1_y = fixed(p1.y),
+                        p2_x = fixed(p2.x),
+                        p2_y = fixed(p2.y)
                     ),
                 };
                 i += 1;
@@ -440,4 +623,171 @@ impl Curve {
             },
         );
     }
+
+    /// Renders the curve as SVG path data from its segments, with every point mapped through
+    /// `transform` first so that tolerances are in output units. With a line tolerance, segments
+    /// straight within it are written as lines, and consecutive collinear lines merged.
+    pub fn render_segments(
+        &self,
+        transform: &Transform,
+        commands: &CurveCommands,
+        line_tolerance: Option<f64>,
+    ) -> String {
+        let mut segments: Vec<Segment> = self
+            .segments()
+            .iter()
+            .map(|segment| segment.transform(transform))
+            .collect();
+        if let Some(tolerance) = line_tolerance {
+            segments = straighten(&segments, tolerance);
+        }
+        if segments.is_empty() {
+            return "".to_string();
+        }
+        let origin = segments[0].start();
+        let mut path = format!("M {} {}", fixed(origin.x), fixed(origin.y));
+        for segment in &segments {
+            match commands {
+                CurveCommands::Cubic => path.push_str(&render_segment(segment)),
+                CurveCommands::Quadratic { tolerance } => {
+                    for quadratic in segment.to_quadratics(*tolerance) {
+                        path.push_str(&render_quadratic(&quadratic));
+                    }
+                }
+                CurveCommands::Arcs { tolerance } => {
+                    for arc in segment.to_arcs(*tolerance) {
+                        path.push_str(&render_arc(&arc));
+                    }
+                }
+            }
+        }
+        return path;
+    }
+}
+
+/// Writes a segment as an SVG path command continuing from its start point
+fn render_segment(segment: &Segment) -> String {
+    match *segment {
+        Segment::Line { to, .. } => format!(" L {} {}", fixed(to.x), fixed(to.y)),
+        Segment::Cubic { c1, c2, to, .. } => format!(
+            " C {} {}, {} {}, {} {}",
+            fixed(c1.x),
+            fixed(c1.y),
+            fixed(c2.x),
+            fixed(c2.y),
+            fixed(to.x),
+            fixed(to.y)
+        ),
+    }
+}
+
+fn render_quadratic(quadratic: &QuadraticSegment) -> String {
+    match *quadratic {
+        QuadraticSegment::Line { to, .. } => format!(" L {} {}", fixed(to.x), fixed(to.y)),
+        QuadraticSegment::Quadratic { control, to, .. } => format!(
+            " Q {} {}, {} {}",
+            fixed(control.x),
+            fixed(control.y),
+            fixed(to.x),
+            fixed(to.y)
+        ),
+    }
+}
+
+fn render_arc(arc: &ArcSegment) -> String {
+    match *arc {
+        ArcSegment::Line { to, .. } => format!(" L {} {}", fixed(to.x), fixed(to.y)),
+        ArcSegment::Arc {
+            to,
+            radius,
+            clockwise,
+            ..
+        } => format!(
+            " A {} {} 0 {} {} {} {}",
+            fixed(radius),
+            fixed(radius),
+            if arc.large_arc() { 1 } else { 0 },
+            if clockwise { 1 } else { 0 },
+            fixed(to.x),
+            fixed(to.y)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a corner, a curve and a corner, running clockwise on screen
+    fn sample() -> Curve {
+        let mut curve = Curve::new(3);
+        curve.tag = vec![Tag::CORNER, Tag::CURVE, Tag::CORNER];
+        curve.c = vec![
+            Point::default(),
+            Point::new(10f64, 0f64),
+            Point::new(10f64, 5f64),
+            Point::new(10f64, 10f64),
+            Point::new(5f64, 12f64),
+            Point::new(0f64, 10f64),
+            Point::default(),
+            Point::new(0f64, 0f64),
+            Point::new(5f64, 0f64),
+        ];
+        curve.vertex = vec![
+            Point::new(10f64, 0f64),
+            Point::new(8f64, 11f64),
+            Point::new(0f64, 0f64),
+        ];
+        curve.alpha = vec![1.5, 0.8, 1.5];
+        curve.alpha0 = vec![1.5, 0.8, 1.5];
+        curve.beta = vec![0.5, 0.25, 0.5];
+        return curve;
+    }
+
+    /// the points every segment runs through, in drawing order
+    fn points(curve: &Curve) -> Vec<(f64, f64)> {
+        curve
+            .segments()
+            .iter()
+            .flat_map(|segment| match *segment {
+                Segment::Line { from, to } => vec![from, to],
+                Segment::Cubic { from, c1, c2, to } => vec![from, c1, c2, to],
+            })
+            .map(|p| (p.x, p.y))
+            .collect()
+    }
+
+    #[test]
+    fn reverse_flips_the_sign_of_the_area() {
+        let curve = sample();
+        let area = curve.signed_area();
+        assert!(area > 0f64);
+        let mut reversed = curve.clone();
+        reversed.reverse();
+        assert!((reversed.signed_area() + area).abs() < 1e-9);
+        assert!(!reversed.is_clockwise());
+
+        let mut expected = points(&curve);
+        expected.reverse();
+        let actual = points(&reversed);
+        // the reversed curve starts at a different segment, so compare from the same point
+        let start = expected.iter().position(|p| *p == actual[0]).unwrap();
+        expected.rotate_left(start);
+        assert_eq!(actual, expected);
+
+        reversed.reverse();
+        assert_eq!(points(&reversed), points(&curve));
+        assert_eq!(reversed.beta, curve.beta);
+    }
+
+    #[test]
+    fn orient_reverses_only_when_needed() {
+        let mut curve = sample();
+        curve.orient(true);
+        assert!(curve.signed_area() > 0f64);
+        curve.orient(false);
+        assert!(curve.signed_area() < 0f64);
+        curve.orient(false);
+        assert!(curve.signed_area() < 0f64);
+    }
 }
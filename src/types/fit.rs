@@ -0,0 +1,386 @@
+use super::curve::{Curve, Tag};
+use super::path::Path;
+use super::point::{distance_between, Point};
+use super::segment::Segment;
+
+/// number of boundary points corners and tangents are estimated over, so that they follow the
+/// direction of the boundary rather than the steps between pixels
+const SPAN: usize = 4;
+/// the boundary has a corner where the chords to the points SPAN before and after a point meet
+/// at less than this angle, in degrees
+const CORNER_ANGLE: f64 = 120f64;
+/// number of Newton iterations refining the parameters of the points before splitting
+const REPARAMETERIZATIONS: usize = 4;
+
+fn add(a: Point, b: Point) -> Point {
+    Point::new(a.x + b.x, a.y + b.y)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    Point::new(a.x - b.x, a.y - b.y)
+}
+
+fn scale(a: Point, s: f64) -> Point {
+    Point::new(a.x * s, a.y * s)
+}
+
+fn dot(a: Point, b: Point) -> f64 {
+    a.x * b.x + a.y * b.y
+}
+
+fn normalize(a: Point) -> Point {
+    let length = a.x.hypot(a.y);
+    if length == 0f64 {
+        return a;
+    }
+    return scale(a, 1f64 / length);
+}
+
+/// Fits a closed curve of cubic Bezier segments to the pixel boundary of a path, with the
+/// least-squares method of Schneider's "An Algorithm for Automatically Fitting Digitized Curves".
+///
+/// The boundary is cut at its corners, and the middles of the boundary steps between two corners
+/// fitted with a single cubic when none of them is farther than `max_error` from it, or split at
+/// the farthest one otherwise. Unlike {@link Curve::smooth}, this gives few long segments on smooth
+/// outlines. Every segment of the result is tagged CURVE.
+pub fn fit_least_squares(path: &Path, max_error: f64) -> Curve {
+    let points = &path.pt;
+    let n = points.len();
+    let mut beziers: Vec<[Point; 4]> = vec![];
+    if n >= 3 {
+        let span = SPAN.min(n / 4).max(1);
+        let corners = find_corners(points, span, true);
+        if corners.is_empty() {
+            // a smooth ring, cut anywhere with the same tangent on both sides
+            let tangent = normalize(sub(points[span], points[n - span]));
+            let mut ring: Vec<Point> = (0..n).map(|i| midpoint(points, i)).collect();
+            ring.push(ring[0]);
+            fit_cubic(
+                &ring,
+                tangent,
+                scale(tangent, -1f64),
+                max_error,
+                &mut beziers,
+                0,
+            );
+        } else {
+            for (k, start) in corners.iter().enumerate() {
+                let mut end = corners[(k + 1) % corners.len()];
+                if end <= *start {
+                    end += n;
+                }
+                let mut run: Vec<Point> = (*start..end).map(|i| midpoint(points, i)).collect();
+                // the corners themselves stay on the boundary
+                run[0] = points[*start];
+                run.push(points[end % n]);
+                let last = run.len() - 1;
+                let reach = span.min(last);
+                let t1 = normalize(sub(run[reach], run[0]));
+                let t2 = normalize(sub(run[last - reach], run[last]));
+                fit_cubic(&run, t1, t2, max_error, &mut beziers, 0);
+            }
+        }
+    }
+
+    let mut curve = Curve::new(beziers.len());
+    for bezier in &beziers {
+        curve.tag.push(Tag::CURVE);
+        curve.c.push(bezier[1]);
+        curve.c.push(bezier[2]);
+        curve.c.push(bezier[3]);
+        curve.vertex.push(bezier[3]);
+        curve.alpha.push(1f64);
+        curve.alpha0.push(1f64);
+        curve.beta.push(0.5);
+    }
+    return curve;
+}
+
+/// Returns the pixel boundary of a path as a curve of corners, one for every point where the
+/// boundary turns, so that collinear points are merged. Each corner ends halfway along the edge
+/// to the next one.
+pub fn fit_pixels(path: &Path) -> Curve {
+    let points = &path.pt;
+    let n = points.len();
+    let corners: Vec<Point> = (0..n)
+        .filter(|i| {
+            let (previous, point, next) =
+                (points[(i + n - 1) % n], points[*i], points[(i + 1) % n]);
+            (point.x - previous.x, point.y - previous.y) != (next.x - point.x, next.y - point.y)
+        })
+        .map(|i| points[i])
+        .collect();
+
+    let m = corners.len();
+    let mut curve = Curve::new(m);
+    for i in 0..m {
+        let (corner, next) = (corners[i], corners[(i + 1) % m]);
+        curve.tag.push(Tag::CORNER);
+        curve.c.push(corner);
+        curve.c.push(corner);
+        curve.c.push(Point::new(
+            (corner.x + next.x) / 2f64,
+            (corner.y + next.y) / 2f64,
+        ));
+        curve.vertex.push(corner);
+        curve.alpha.push(1f64);
+        curve.alpha0.push(1f64);
+        curve.beta.push(0.5);
+    }
+    return curve;
+}
+
+/// Fits a polyline with cubic Bezier segments, no point being farther than `max_error` from
+/// them. The polyline is cut at its corners, and a closed polyline, whose last point connects
+/// back to the first, gets a smooth joint there unless it has corners.
+pub fn fit_polyline(points: &[Point], closed: bool, max_error: f64) -> Vec<Segment> {
+    let n = points.len();
+    let mut beziers = vec![];
+    if n < 2 {
+        return vec![];
+    }
+    let closed = closed && n >= 3;
+    let span = SPAN.min(n / 4).max(1);
+    let mut cuts = find_corners(points, span, closed);
+    if closed && cuts.is_empty() {
+        let tangent = normalize(sub(points[span], points[n - span]));
+        let mut ring = points.to_vec();
+        ring.push(points[0]);
+        fit_cubic(
+            &ring,
+            tangent,
+            scale(tangent, -1f64),
+            max_error,
+            &mut beziers,
+            0,
+        );
+        return beziers.iter().map(cubic).collect();
+    }
+    if !closed {
+        if cuts.first() != Some(&0) {
+            cuts.insert(0, 0);
+        }
+        if cuts.last() != Some(&(n - 1)) {
+            cuts.push(n - 1);
+        }
+    }
+    let runs = if closed { cuts.len() } else { cuts.len() - 1 };
+    for k in 0..runs {
+        let (start, mut end) = (cuts[k], cuts[(k + 1) % cuts.len()]);
+        if end <= start {
+            end += n;
+        }
+        let run: Vec<Point> = (start..=end).map(|i| points[i % n]).collect();
+        let last = run.len() - 1;
+        let reach = span.min(last);
+        let t1 = normalize(sub(run[reach], run[0]));
+        let t2 = normalize(sub(run[last - reach], run[last]));
+        fit_cubic(&run, t1, t2, max_error, &mut beziers, 0);
+    }
+    return beziers.iter().map(cubic).collect();
+}
+
+/// return the middle of the boundary step from point i to the next one. Fitting the middles of
+/// the steps halves the staircase of pixel boundaries.
+fn midpoint(points: &[Point], i: usize) -> Point {
+    let (a, b) = (points[i % points.len()], points[(i + 1) % points.len()]);
+    Point::new((a.x + b.x) / 2f64, (a.y + b.y) / 2f64)
+}
+
+/// Returns indexes of the points where the boundary turns sharper than CORNER_ANGLE, keeping
+/// only the sharpest point within `span` of each other. Points of an open polyline closer than
+/// `span` to either end are never corners.
+fn find_corners(points: &[Point], span: usize, closed: bool) -> Vec<usize> {
+    let n = points.len();
+    let angles: Vec<f64> = (0..n)
+        .map(|i| {
+            if !closed && (i < span || i + span >= n) {
+                return 180f64;
+            }
+            let p = points[i];
+            let back = normalize(sub(points[(i + n - span) % n], p));
+            let ahead = normalize(sub(points[(i + span) % n], p));
+            dot(back, ahead).max(-1f64).min(1f64).acos().to_degrees()
+        })
+        .collect();
+    return (0..n)
+        .filter(|i| {
+            let angle = angles[*i];
+            angle < CORNER_ANGLE
+                && (1..=span).all(|d| {
+                    // ties go to the first point
+                    angle < angles[(i + n - d) % n] && angle <= angles[(i + d) % n]
+                })
+        })
+        .collect();
+}
+
+/// Fits the points with cubics from the first to the last point, leaving the first along `t1`
+/// and arriving at the last from along `t2`, both pointing into the curve
+fn fit_cubic(
+    points: &[Point],
+    t1: Point,
+    t2: Point,
+    max_error: f64,
+    beziers: &mut Vec<[Point; 4]>,
+    depth: usize,
+) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if points.len() == 2 {
+        let third = distance_between(first, last) / 3f64;
+        beziers.push([
+            first,
+            add(first, scale(t1, third)),
+            add(last, scale(t2, third)),
+            last,
+        ]);
+        return;
+    }
+
+    let mut u = chord_length_parameters(points);
+    let mut bezier = generate_bezier(points, &u, t1, t2);
+    let (mut error, mut split) = max_distance(points, &bezier, &u);
+    if error > max_error && error <= 4f64 * max_error {
+        for _ in 0..REPARAMETERIZATIONS {
+            u = reparameterize(points, &bezier, &u);
+            bezier = generate_bezier(points, &u, t1, t2);
+            let (e, s) = max_distance(points, &bezier, &u);
+            error = e;
+            split = s;
+            if error <= max_error {
+                break;
+            }
+        }
+    }
+    // 2^16 pieces is far below any sensible error, and guards against NaN
+    if error <= max_error || depth >= 16 {
+        beziers.push(bezier);
+        return;
+    }
+
+    let reach = SPAN.min(split).min(points.len() - 1 - split);
+    let center = normalize(sub(points[split - reach], points[split + reach]));
+    fit_cubic(&points[..=split], t1, center, max_error, beziers, depth + 1);
+    fit_cubic(
+        &points[split..],
+        scale(center, -1f64),
+        t2,
+        max_error,
+        beziers,
+        depth + 1,
+    );
+}
+
+/// return parameters of the points in [0..1], proportional to the length of the polyline
+/// through them
+fn chord_length_parameters(points: &[Point]) -> Vec<f64> {
+    let mut u = vec![0f64];
+    for i in 1..points.len() {
+        let previous = u[i - 1];
+        u.push(previous + distance_between(points[i - 1], points[i]));
+    }
+    let total = u[u.len() - 1];
+    if total > 0f64 {
+        for value in u.iter_mut() {
+            *value /= total;
+        }
+    }
+    return u;
+}
+
+/// Solves for the lengths of the end tangents that give the cubic closest to the points at
+/// their parameters, in the least-squares sense
+fn generate_bezier(points: &[Point], u: &[f64], t1: Point, t2: Point) -> [Point; 4] {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let mut c = [[0f64; 2]; 2];
+    let mut x = [0f64; 2];
+    for (p, t) in points.iter().zip(u.iter()) {
+        let s = 1f64 - t;
+        let (b0, b1, b2, b3) = (s * s * s, 3f64 * s * s * t, 3f64 * s * t * t, t * t * t);
+        let (a1, a2) = (scale(t1, b1), scale(t2, b2));
+        c[0][0] += dot(a1, a1);
+        c[0][1] += dot(a1, a2);
+        c[1][1] += dot(a2, a2);
+        let rest = sub(*p, add(scale(first, b0 + b1), scale(last, b2 + b3)));
+        x[0] += dot(a1, rest);
+        x[1] += dot(a2, rest);
+    }
+    c[1][0] = c[0][1];
+
+    let det = c[0][0] * c[1][1] - c[1][0] * c[0][1];
+    let (mut alpha1, mut alpha2) = if det != 0f64 {
+        (
+            (x[0] * c[1][1] - x[1] * c[0][1]) / det,
+            (c[0][0] * x[1] - c[1][0] * x[0]) / det,
+        )
+    } else {
+        (0f64, 0f64)
+    };
+    // fall back to the heuristic of Wu and Barsky when the solution is degenerate
+    let length = distance_between(first, last);
+    if alpha1 < 1e-6 * length || alpha2 < 1e-6 * length {
+        alpha1 = length / 3f64;
+        alpha2 = length / 3f64;
+    }
+    return [
+        first,
+        add(first, scale(t1, alpha1)),
+        add(last, scale(t2, alpha2)),
+        last,
+    ];
+}
+
+/// return the largest distance between a point and the cubic at its parameter, along with the
+/// index of that point, never the first or last
+fn max_distance(points: &[Point], bezier: &[Point; 4], u: &[f64]) -> (f64, usize) {
+    let segment = cubic(bezier);
+    let mut max = (0f64, points.len() / 2);
+    for i in 1..points.len() - 1 {
+        let distance = distance_between(segment.point_at(u[i]), points[i]);
+        if distance > max.0 {
+            max = (distance, i);
+        }
+    }
+    return max;
+}
+
+/// Improves the parameter of every point with a Newton-Raphson step towards the nearest point
+/// of the cubic
+fn reparameterize(points: &[Point], bezier: &[Point; 4], u: &[f64]) -> Vec<f64> {
+    let segment = cubic(bezier);
+    points
+        .iter()
+        .zip(u.iter())
+        .map(|(p, t)| {
+            let q = segment.point_at(*t);
+            let d1 = segment.derivative_at(*t);
+            let s = 1f64 - t;
+            let d2 = add(
+                scale(
+                    add(sub(bezier[2], scale(bezier[1], 2f64)), bezier[0]),
+                    6f64 * s,
+                ),
+                scale(
+                    add(sub(bezier[3], scale(bezier[2], 2f64)), bezier[1]),
+                    6f64 * t,
+                ),
+            );
+            let difference = sub(q, *p);
+            let numerator = dot(difference, d1);
+            let denominator = dot(d1, d1) + dot(difference, d2);
+            if denominator == 0f64 {
+                return *t;
+            }
+            (t - numerator / denominator).max(0f64).min(1f64)
+        })
+        .collect()
+}
+
+fn cubic(bezier: &[Point; 4]) -> Segment {
+    Segment::Cubic {
+        from: bezier[0],
+        c1: bezier[1],
+        c2: bezier[2],
+        to: bezier[3],
+    }
+}
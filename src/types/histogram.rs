@@ -2,7 +2,6 @@ use image::{ImageBuffer, Rgba};
 use num::clamp;
 use std::cmp::min;
 use std::collections::HashMap;
-use utils::luminance;
 
 const COLOR_DEPTH: usize = 256;
 const COLOR_RANGE_END: usize = COLOR_DEPTH - 1;
@@ -84,13 +83,13 @@ pub struct Stats {
  */
 #[derive(Clone)]
 pub struct Histogram {
-    pub red: Vec<u8>,
+    pub red: Vec<u32>,
     red_sorted_indexes: Vec<usize>, // null
-    pub green: Vec<u8>,
+    pub green: Vec<u32>,
     green_sorted_indexes: Vec<usize>, // null
-    pub blue: Vec<u8>,
+    pub blue: Vec<u32>,
     blue_sorted_indexes: Vec<usize>, // null
-    pub lum: Vec<u8>,
+    pub lum: Vec<u32>,
     lum_sorted_indexes: Vec<usize>,           // null
     pub pixels: usize,                        // = 0
     pub cached_stats: HashMap<String, Stats>, // = {}
@@ -98,13 +97,15 @@ pub struct Histogram {
 }
 
 impl Histogram {
-    pub fn new(raw_image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> Histogram {
+    /// Collects per channel counts from the image, and counts for `levels` (one value per pixel,
+    /// as read by the tracer from its source channel) in place of luminance
+    pub fn new(raw_image: ImageBuffer<Rgba<u8>, Vec<u8>>, levels: &[u8]) -> Histogram {
         let pixel_data = raw_image.pixels();
         let pixels = (raw_image.width() * raw_image.height()) as usize;
-        let mut red = Vec::with_capacity(COLOR_DEPTH);
-        let mut green = Vec::with_capacity(COLOR_DEPTH);
-        let mut blue = Vec::with_capacity(COLOR_DEPTH);
-        let mut lum = Vec::with_capacity(COLOR_DEPTH);
+        let mut red = vec![0u32; COLOR_DEPTH];
+        let mut green = vec![0u32; COLOR_DEPTH];
+        let mut blue = vec![0u32; COLOR_DEPTH];
+        let mut lum = vec![0u32; COLOR_DEPTH];
         pixel_data.for_each(|pixel| {
             let r = pixel[0];
             let g = pixel[1];
@@ -112,8 +113,10 @@ impl Histogram {
             red[r as usize] += 1;
             green[g as usize] += 1;
             blue[b as usize] += 1;
-            lum[luminance(r, g, b)] += 1;
         });
+        for level in levels {
+            lum[*level as usize] += 1;
+        }
         return Histogram {
             red: red.clone(),
             red_sorted_indexes: Histogram::sort_indexes(red),
@@ -129,12 +132,8 @@ impl Histogram {
         };
     }
 
-    fn sort_indexes(channel: Vec<u8>) -> Vec<usize> {
-        let mut indexes = Vec::with_capacity(COLOR_DEPTH);
-
-        for i in 0..COLOR_DEPTH {
-            indexes[i] = i
-        }
+    fn sort_indexes(channel: Vec<u32>) -> Vec<usize> {
+        let mut indexes: Vec<usize> = (0..COLOR_DEPTH).collect();
         indexes.sort_by(|a, b| channel[a.to_owned()].cmp(&channel[b.to_owned()]));
 
         return indexes;
@@ -169,9 +168,9 @@ impl Histogram {
      */
     fn thresholding_build_lookup_table(&mut self) -> Vec<u32> {
         // 3 vectors with size 65,536 each
-        let mut P: Vec<u32> = Vec::with_capacity(COLOR_DEPTH.pow(2));
-        let mut S: Vec<u32> = Vec::with_capacity(COLOR_DEPTH.pow(2));
-        let mut H: Vec<u32> = Vec::with_capacity(COLOR_DEPTH.pow(2));
+        let mut P: Vec<u32> = vec![0; COLOR_DEPTH.pow(2)];
+        let mut S: Vec<u32> = vec![0; COLOR_DEPTH.pow(2)];
+        let mut H: Vec<u32> = vec![0; COLOR_DEPTH.pow(2)];
         let pixels_total = self.pixels;
 
         // diagonal
@@ -360,7 +359,7 @@ impl Histogram {
             None => 1,
         };
         let mut dominant_index: Option<usize> = None;
-        let mut dominant_value: Option<u8> = None;
+        let mut dominant_value: Option<u32> = None;
 
         if min == max {
             return if colors[min as usize] != 0 {
@@ -459,7 +458,7 @@ impl Histogram {
             }
             pixels_iterated += pixels;
             sum_of_deviations += (pixel_value - mean_value as usize).pow(2) * pixels as usize;
-            if median_value == 0 && pixels_iterated >= median_pixel_index as u8 {
+            if median_value == 0 && pixels_iterated >= median_pixel_index as u32 {
                 median_value = pixel_value as u8
             }
         }
@@ -495,3 +494,20 @@ impl Histogram {
         return self.cached_stats[&cache_key].clone();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_every_pixel_without_overflow() {
+        let image = ImageBuffer::from_pixel(20, 20, Rgba([10u8, 20, 30, 255]));
+        let levels = vec![200u8; 400];
+        let histogram = Histogram::new(image, &levels);
+        assert_eq!(histogram.red[10], 400);
+        assert_eq!(histogram.green[20], 400);
+        assert_eq!(histogram.blue[30], 400);
+        assert_eq!(histogram.lum[200], 400);
+        assert_eq!(histogram.lum.iter().sum::<u32>(), 400);
+    }
+}
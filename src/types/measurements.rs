@@ -0,0 +1,269 @@
+use super::path::Path;
+use super::point::{cross_product, Point};
+use super::segment::Segment;
+use super::shape::Shape;
+use std::f64::consts::PI;
+
+/// number of straight pieces each Bezier segment is sampled into for moments and convex hull
+const SAMPLES: usize = 16;
+
+/// Measurements of a single traced shape, taken from its curves rather than its pixels
+///
+/// Lengths are in the units of the measured shapes, areas in those units squared.
+#[derive(Clone)]
+pub struct ShapeMeasurements {
+    /// position of the shape in the list that was measured
+    pub index: usize,
+    /// area enclosed by the outline minus the area of the holes
+    pub area: f64,
+    /// arc length of the outline and of every hole
+    pub perimeter: f64,
+    pub centroid: Point,
+    /// angle of the major axis in radians, measured from the x axis towards the y axis
+    pub orientation: f64,
+    /// eccentricity of the ellipse with the same second moments, 0 for a circle
+    pub eccentricity: f64,
+    /// area of the convex hull of the outline
+    pub convex_area: f64,
+    /// area divided by convex area
+    pub solidity: f64,
+    /// 4 * pi * area / perimeter^2, 1 for a circle
+    pub circularity: f64,
+    /// number of holes in the shape
+    pub holes: usize,
+    /// number of outlines enclosing the shape, 0 for shapes at the top level
+    pub depth: usize,
+}
+
+/// Area and first and second moments of area of a closed polygon
+#[derive(Default)]
+pub struct Moments {
+    pub area: f64,
+    pub x: f64,
+    pub y: f64,
+    pub xx: f64,
+    pub yy: f64,
+    pub xy: f64,
+}
+
+impl Moments {
+    /// Integrates over the polygon using Green's theorem. The moments are normalized so that
+    /// they come out positive however the polygon is oriented.
+    pub fn of(polygon: &[Point]) -> Moments {
+        let mut m = Moments::default();
+        let n = polygon.len();
+        for i in 0..n {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            let cross = a.x * b.y - b.x * a.y;
+            m.area += cross / 2f64;
+            m.x += (a.x + b.x) * cross / 6f64;
+            m.y += (a.y + b.y) * cross / 6f64;
+            m.xx += (a.x * a.x + a.x * b.x + b.x * b.x) * cross / 12f64;
+            m.yy += (a.y * a.y + a.y * b.y + b.y * b.y) * cross / 12f64;
+            m.xy += (a.x * b.y + 2f64 * a.x * a.y + 2f64 * b.x * b.y + b.x * a.y) * cross / 24f64;
+        }
+        if m.area < 0f64 {
+            m.scale(-1f64);
+        }
+        return m;
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.area *= factor;
+        self.x *= factor;
+        self.y *= factor;
+        self.xx *= factor;
+        self.yy *= factor;
+        self.xy *= factor;
+    }
+
+    fn subtract(&mut self, other: &Moments) {
+        self.area -= other.area;
+        self.x -= other.x;
+        self.y -= other.y;
+        self.xx -= other.xx;
+        self.yy -= other.yy;
+        self.xy -= other.xy;
+    }
+}
+
+/// Samples the curve of a path into a closed polygon
+fn polygon(path: &Path) -> Vec<Point> {
+    let mut points = vec![];
+    for segment in path.curve.segments() {
+        let samples = segment.sample(SAMPLES);
+        points.extend_from_slice(&samples[..samples.len() - 1]);
+    }
+    return points;
+}
+
+/// Returns the area enclosed by the curve of a path, exact for the Bezier segments
+fn curve_area(path: &Path) -> f64 {
+    let area: f64 = path.curve.segments().iter().map(Segment::area).sum();
+    return area.abs();
+}
+
+fn curve_length(path: &Path) -> f64 {
+    path.curve.segments().iter().map(Segment::length).sum()
+}
+
+/// return (b-a)x(c-a), positive when a, b, c turn counterclockwise in a y-up system
+fn turn(a: Point, b: Point, c: Point) -> f64 {
+    cross_product(
+        Point::new(b.x - a.x, b.y - a.y),
+        Point::new(c.x - a.x, c.y - a.y),
+    )
+}
+
+/// Returns the convex hull of the given points counterclockwise in a y-up system, using
+/// Andrew's monotone chain
+pub fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap()
+            .then(a.y.partial_cmp(&b.y).unwrap())
+    });
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let mut hull: Vec<Point> = vec![];
+    for pass in 0..2 {
+        let start = hull.len();
+        for point in sorted.iter() {
+            while hull.len() >= start + 2
+                && turn(hull[hull.len() - 2], hull[hull.len() - 1], *point) <= 0f64
+            {
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    return hull;
+}
+
+/// Measures every shape from its curves, so that the paths must have been processed.
+///
+/// Area and perimeter are integrated over the Bezier segments directly. Centroid and second
+/// moments, and with them orientation and eccentricity, are taken from the curves sampled into
+/// polygons, as is the convex hull.
+pub fn measure_shapes(shapes: &[Shape]) -> Vec<ShapeMeasurements> {
+    let mut measurements = vec![];
+    for (index, shape) in shapes.iter().enumerate() {
+        let outline = polygon(&shape.outline);
+        let mut moments = Moments::of(&outline);
+        let mut area = curve_area(&shape.outline);
+        let mut perimeter = curve_length(&shape.outline);
+        for hole in &shape.holes {
+            moments.subtract(&Moments::of(&polygon(hole)));
+            area -= curve_area(hole);
+            perimeter += curve_length(hole);
+        }
+
+        let (mut centroid, mut orientation, mut eccentricity) = (Point::default(), 0f64, 0f64);
+        if moments.area > 0f64 {
+            let (cx, cy) = (moments.x / moments.area, moments.y / moments.area);
+            let mu20 = moments.xx / moments.area - cx * cx;
+            let mu02 = moments.yy / moments.area - cy * cy;
+            let mu11 = moments.xy / moments.area - cx * cy;
+            let common = (((mu20 - mu02) / 2f64).powi(2) + mu11 * mu11).sqrt();
+            let major = (mu20 + mu02) / 2f64 + common;
+            let minor = (mu20 + mu02) / 2f64 - common;
+            centroid = Point::new(cx, cy);
+            orientation = 0.5 * (2f64 * mu11).atan2(mu20 - mu02);
+            if major > 0f64 {
+                eccentricity = (1f64 - (minor / major).max(0f64)).sqrt();
+            }
+        }
+
+        let convex_area = Moments::of(&convex_hull(&outline)).area;
+        // the first pixel of the outline, which is inside every outline enclosing this one
+        let center = shape.outline.first_pixel_center();
+        let (x, y) = (center.x, center.y);
+        let depth = shapes
+            .iter()
+            .enumerate()
+            .filter(|(i, other)| *i != index && other.outline.contains(x, y))
+            .count();
+
+        measurements.push(ShapeMeasurements {
+            index,
+            area,
+            perimeter,
+            centroid,
+            orientation,
+            eccentricity,
+            convex_area,
+            solidity: if convex_area > 0f64 {
+                area / convex_area
+            } else {
+                0f64
+            },
+            circularity: if perimeter > 0f64 {
+                4f64 * PI * area / (perimeter * perimeter)
+            } else {
+                0f64
+            },
+            holes: shape.holes.len(),
+            depth,
+        });
+    }
+    return measurements;
+}
+
+/// Formats measurements as CSV with a header row
+pub fn to_csv(measurements: &[ShapeMeasurements]) -> String {
+    let mut csv = String::from(
+        "index,area,perimeter,centroid_x,centroid_y,orientation,eccentricity,convex_area,\
+         solidity,circularity,holes,depth\n",
+    );
+    for m in measurements {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            m.index,
+            m.area,
+            m.perimeter,
+            m.centroid.x,
+            m.centroid.y,
+            m.orientation,
+            m.eccentricity,
+            m.convex_area,
+            m.solidity,
+            m.circularity,
+            m.holes,
+            m.depth
+        ));
+    }
+    return csv;
+}
+
+/// Formats measurements as a JSON array of objects
+pub fn to_json(measurements: &[ShapeMeasurements]) -> String {
+    let objects: Vec<String> = measurements
+        .iter()
+        .map(|m| {
+            format!(
+                "{{\"index\":{},\"area\":{},\"perimeter\":{},\"centroid\":[{},{}],\
+                 \"orientation\":{},\"eccentricity\":{},\"convexArea\":{},\"solidity\":{},\
+                 \"circularity\":{},\"holes\":{},\"depth\":{}}}",
+                m.index,
+                m.area,
+                m.perimeter,
+                m.centroid.x,
+                m.centroid.y,
+                m.orientation,
+                m.eccentricity,
+                m.convex_area,
+                m.solidity,
+                m.circularity,
+                m.holes,
+                m.depth
+            )
+        })
+        .collect();
+    return format!("[{}]", objects.join(","));
+}
@@ -0,0 +1,137 @@
+/// Shape of the neighbourhood a morphological operation looks at
+#[derive(PartialEq, Clone)]
+pub enum StructuringElement {
+    /// square of (2 * radius + 1) pixels per side
+    Square(usize),
+    /// plus sign with arms of `radius` pixels
+    Cross(usize),
+    /// disk of the given radius
+    Disk(usize),
+    /// arbitrary mask of width * height pixels centered on the pixel, 1 marking members
+    Custom {
+        width: usize,
+        height: usize,
+        data: Vec<u8>,
+    },
+}
+
+impl StructuringElement {
+    /// Returns offsets of the element members relative to its center
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        let mut offsets = vec![];
+        match self {
+            StructuringElement::Square(radius)
+            | StructuringElement::Cross(radius)
+            | StructuringElement::Disk(radius) => {
+                let r = *radius as isize;
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        let member = match self {
+                            StructuringElement::Cross(_) => dx == 0 || dy == 0,
+                            StructuringElement::Disk(_) => dx * dx + dy * dy <= r * r,
+                            _ => true,
+                        };
+                        if member {
+                            offsets.push((dx, dy));
+                        }
+                    }
+                }
+            }
+            StructuringElement::Custom {
+                width,
+                height,
+                data,
+            } => {
+                let (cx, cy) = ((*width / 2) as isize, (*height / 2) as isize);
+                for y in 0..*height {
+                    for x in 0..*width {
+                        if data[y * width + x] != 0 {
+                            offsets.push((x as isize - cx, y as isize - cy));
+                        }
+                    }
+                }
+            }
+        }
+        return offsets;
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub enum MorphologyOps {
+    Dilate,
+    Erode,
+    /// erosion followed by dilation, removes thin protrusions and specks
+    Open,
+    /// dilation followed by erosion, fills thin gaps and pinholes
+    Close,
+}
+
+/// A morphological operation applied to the binary bitmap before it is decomposed into paths
+#[derive(Clone)]
+pub struct Morphology {
+    pub op: MorphologyOps,
+    pub element: StructuringElement,
+}
+
+/// Sets every pixel that has a set pixel anywhere under the reflected element
+pub fn dilate(data: &[u8], width: usize, height: usize, element: &StructuringElement) -> Vec<u8> {
+    let offsets = element.offsets();
+    let mut out = vec![0u8; data.len()];
+    for y in 0..height {
+        for x in 0..width {
+            if data[y * width + x] == 0 {
+                continue;
+            }
+            for (dx, dy) in &offsets {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height {
+                    out[ny as usize * width + nx as usize] = 1;
+                }
+            }
+        }
+    }
+    return out;
+}
+
+/// Keeps only the pixels whose whole neighbourhood under the element is set. Neighbours
+/// outside of the image are ignored, so shapes touching the edge do not shrink from it.
+pub fn erode(data: &[u8], width: usize, height: usize, element: &StructuringElement) -> Vec<u8> {
+    let offsets = element.offsets();
+    let mut out = vec![0u8; data.len()];
+    for y in 0..height {
+        for x in 0..width {
+            if data[y * width + x] == 0 {
+                continue;
+            }
+            let covered = offsets.iter().all(|(dx, dy)| {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                nx < 0
+                    || ny < 0
+                    || nx as usize >= width
+                    || ny as usize >= height
+                    || data[ny as usize * width + nx as usize] != 0
+            });
+            if covered {
+                out[y * width + x] = 1;
+            }
+        }
+    }
+    return out;
+}
+
+/// Applies a morphological operation to binary pixel data
+pub fn apply(data: &[u8], width: usize, height: usize, morphology: &Morphology) -> Vec<u8> {
+    let element = &morphology.element;
+    match morphology.op {
+        MorphologyOps::Dilate => dilate(data, width, height, element),
+        MorphologyOps::Erode => erode(data, width, height, element),
+        MorphologyOps::Open => {
+            let eroded = erode(data, width, height, element);
+            dilate(&eroded, width, height, element)
+        }
+        MorphologyOps::Close => {
+            let dilated = dilate(data, width, height, element);
+            erode(&dilated, width, height, element)
+        }
+    }
+}
@@ -3,6 +3,7 @@ use types::curve::Curve;
 use types::point::{cross_product, Point};
 use types::quad::Quad;
 use types::sum::Sum;
+use types::transform::Transform;
 use utils::{cyclic, modulo, sign};
 #[derive(Clone)]
 pub struct Path {
@@ -54,6 +55,53 @@ impl Path {
         }
     }
 
+    /// Returns the center of the first pixel inside the path. Decomposition starts every path at
+    /// the top left corner of its first pixel, going down the left side of the pixel and coming
+    /// back along its top side, so the center is halfway between the second and the last point.
+    /// This holds after an affine transform too.
+    pub fn first_pixel_center(&self) -> Point {
+        let (down, right) = (self.pt[1], self.pt[self.pt.len() - 1]);
+        Point::new((down.x + right.x) / 2f64, (down.y + right.y) / 2f64)
+    }
+
+    /// Returns the path with its pixel outline and curve mapped through the given transform,
+    /// for output. The area is scaled along, while the caches used for fitting are dropped.
+    pub fn transform(&self, transform: &Transform) -> Path {
+        let mut path = self.clone();
+        path.pt = self.pt.iter().map(|p| transform.apply(*p)).collect();
+        path.min_x = path.pt.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+        path.min_y = path.pt.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+        path.max_x = path
+            .pt
+            .iter()
+            .map(|p| p.x)
+            .fold(f64::NEG_INFINITY, f64::max);
+        path.max_y = path
+            .pt
+            .iter()
+            .map(|p| p.y)
+            .fold(f64::NEG_INFINITY, f64::max);
+        path.area = self.area * (transform.a * transform.d - transform.b * transform.c).abs();
+        path.curve = self.curve.transform(transform);
+        path.sums = vec![];
+        path.lon = vec![];
+        return path;
+    }
+
+    /// Tests whether a point lies inside the pixel outline of the path, using the even-odd rule
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let mut inside = false;
+        let n = self.pt.len();
+        for i in 0..n {
+            let a = self.pt[i];
+            let b = self.pt[modulo(i + 1, n)];
+            if (a.y > y) != (b.y > y) && x < a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x) {
+                inside = !inside;
+            }
+        }
+        return inside;
+    }
+
     pub fn calc_sums(&mut self) -> &mut Self {
         self.x0 = self.pt[0].x;
         self.y0 = self.pt[0].y;
@@ -0,0 +1,39 @@
+use super::path::Path;
+use super::point::Point;
+use super::shape::Shape;
+
+/// A traced shape as straight edged polygons. Every ring is closed, its last point connecting
+/// back to the first.
+#[derive(Clone)]
+pub struct Polygon {
+    pub outline: Vec<Point>,
+    pub holes: Vec<Vec<Point>>,
+}
+
+impl Polygon {
+    /// Flattens the curves of a shape into polygons no farther than `tolerance` from them
+    pub fn from_curves(shape: &Shape, tolerance: f64) -> Polygon {
+        Polygon {
+            outline: shape.outline.curve.flatten(tolerance),
+            holes: shape
+                .holes
+                .iter()
+                .map(|hole| hole.curve.flatten(tolerance))
+                .collect(),
+        }
+    }
+
+    /// Takes the optimal polygons found by {@link Path::best_polygon}, with vertices on pixel
+    /// corners, instead of the curves fitted to them
+    pub fn from_optimal_polygons(shape: &Shape) -> Polygon {
+        Polygon {
+            outline: optimal_polygon(&shape.outline),
+            holes: shape.holes.iter().map(optimal_polygon).collect(),
+        }
+    }
+}
+
+/// Returns the vertices of the optimal polygon of a path
+fn optimal_polygon(path: &Path) -> Vec<Point> {
+    path.po.iter().map(|i| path.pt[*i]).collect()
+}
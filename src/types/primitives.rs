@@ -0,0 +1,288 @@
+use super::curve::{fixed, Curve};
+use super::measurements::{convex_hull, Moments};
+use super::point::Point;
+use super::polygon::Polygon;
+use super::shape::Shape;
+use super::simplify::simplify;
+use super::transform::Transform;
+use constants::SimplificationMethods;
+use std::f64::consts::PI;
+
+/// A geometric primitive a traced curve was recognized as, in pixel coordinates. Angles are in
+/// degrees, clockwise on screen.
+#[derive(Clone)]
+pub enum Primitive {
+    Circle {
+        center: Point,
+        radius: f64,
+    },
+    Ellipse {
+        center: Point,
+        rx: f64,
+        ry: f64,
+        angle: f64,
+    },
+    /// rectangle, rotated by angle around its center, with corners rounded by radius
+    Rect {
+        center: Point,
+        width: f64,
+        height: f64,
+        angle: f64,
+        radius: f64,
+    },
+    /// regular polygon with at most 12 vertices
+    RegularPolygon {
+        points: Vec<Point>,
+    },
+}
+
+/// Primitives recognized for the outline and every hole of a shape, None where the curve is not
+/// close enough to any primitive
+#[derive(Clone)]
+pub struct RecognizedShape {
+    pub outline: Option<Primitive>,
+    pub holes: Vec<Option<Primitive>>,
+}
+
+/// Recognizes the outline and holes of a shape as primitives within `tolerance` pixels
+pub fn recognize_shape(shape: &Shape, tolerance: f64) -> RecognizedShape {
+    RecognizedShape {
+        outline: recognize(&shape.outline.curve, tolerance),
+        holes: shape
+            .holes
+            .iter()
+            .map(|hole| recognize(&hole.curve, tolerance))
+            .collect(),
+    }
+}
+
+/// Recognizes a closed curve as a circle, rectangle (rotated and rounded), ellipse or regular
+/// polygon, tried in this order, when every point of the curve is within `tolerance` of the
+/// primitive
+pub fn recognize(curve: &Curve, tolerance: f64) -> Option<Primitive> {
+    let points = curve.flatten(tolerance / 4f64);
+    if points.len() < 3 {
+        return None;
+    }
+    let moments = Moments::of(&points);
+    if moments.area <= 0f64 {
+        return None;
+    }
+    let center = Point::new(moments.x / moments.area, moments.y / moments.area);
+    let within = |distance: &dyn Fn(Point) -> f64| points.iter().all(|p| distance(*p) <= tolerance);
+
+    // circle with the same area around the centroid
+    let radius = (moments.area / PI).sqrt();
+    if within(&|p| ((p.x - center.x).hypot(p.y - center.y) - radius).abs()) {
+        return Some(Primitive::Circle { center, radius });
+    }
+
+    if let Some(rect) = fit_rect(&points, moments.area) {
+        if let Primitive::Rect {
+            center,
+            width,
+            height,
+            angle,
+            radius,
+        } = rect
+        {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let (hw, hh) = (width / 2f64, height / 2f64);
+            let distance = |p: Point| {
+                let (dx, dy) = (p.x - center.x, p.y - center.y);
+                let (x, y) = ((dx * cos + dy * sin).abs(), (-dx * sin + dy * cos).abs());
+                let (qx, qy) = (x - hw + radius, y - hh + radius);
+                let outside = qx.max(0f64).hypot(qy.max(0f64));
+                (outside + qx.max(qy).min(0f64) - radius).abs()
+            };
+            if within(&distance) {
+                return Some(rect);
+            }
+        }
+    }
+
+    // ellipse with the same second moments
+    let mu20 = moments.xx / moments.area - center.x * center.x;
+    let mu02 = moments.yy / moments.area - center.y * center.y;
+    let mu11 = moments.xy / moments.area - center.x * center.y;
+    let common = (((mu20 - mu02) / 2f64).powi(2) + mu11 * mu11).sqrt();
+    let major = (mu20 + mu02) / 2f64 + common;
+    let minor = (mu20 + mu02) / 2f64 - common;
+    if minor > 0f64 {
+        let (rx, ry) = (2f64 * major.sqrt(), 2f64 * minor.sqrt());
+        let angle = 0.5 * (2f64 * mu11).atan2(mu20 - mu02);
+        let (sin, cos) = angle.sin_cos();
+        let distance = |p: Point| {
+            let (dx, dy) = (p.x - center.x, p.y - center.y);
+            let (x, y) = (dx * cos + dy * sin, -dx * sin + dy * cos);
+            // first order distance, the implicit function over the length of its gradient
+            let f = (x / rx).powi(2) + (y / ry).powi(2) - 1f64;
+            let gradient = (2f64 * x / (rx * rx)).hypot(2f64 * y / (ry * ry));
+            (f / gradient).abs()
+        };
+        if within(&distance) {
+            return Some(Primitive::Ellipse {
+                center,
+                rx,
+                ry,
+                angle: angle.to_degrees(),
+            });
+        }
+    }
+
+    return fit_regular_polygon(&points, center, tolerance);
+}
+
+/// Fits the minimum area rectangle of the points, rounding its corners so that it keeps the
+/// area enclosed by the points
+fn fit_rect(points: &[Point], area: f64) -> Option<Primitive> {
+    let hull = convex_hull(points);
+    if hull.len() < 3 {
+        return None;
+    }
+    let mut best: Option<(f64, Primitive)> = None;
+    for i in 0..hull.len() {
+        let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+        let length = (b.x - a.x).hypot(b.y - a.y);
+        if length == 0f64 {
+            continue;
+        }
+        let (ux, uy) = ((b.x - a.x) / length, (b.y - a.y) / length);
+        let (mut min_u, mut max_u) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut min_v, mut max_v) = (f64::INFINITY, f64::NEG_INFINITY);
+        for p in &hull {
+            let (u, v) = (p.x * ux + p.y * uy, -p.x * uy + p.y * ux);
+            min_u = min_u.min(u);
+            max_u = max_u.max(u);
+            min_v = min_v.min(v);
+            max_v = max_v.max(v);
+        }
+        let (width, height) = (max_u - min_u, max_v - min_v);
+        if best.as_ref().map_or(false, |(a, _)| *a <= width * height) {
+            continue;
+        }
+        let (cu, cv) = ((min_u + max_u) / 2f64, (min_v + max_v) / 2f64);
+        // corners cut off by the rounding take (4 - pi) r^2 of the area
+        let radius = ((width * height - area).max(0f64) / (4f64 - PI))
+            .sqrt()
+            .min(width.min(height) / 2f64);
+        best = Some((
+            width * height,
+            Primitive::Rect {
+                center: Point::new(cu * ux - cv * uy, cu * uy + cv * ux),
+                width,
+                height,
+                angle: uy.atan2(ux).to_degrees(),
+                radius,
+            },
+        ));
+    }
+    return best.map(|(_, rect)| rect);
+}
+
+/// Simplifies the points into a polygon within the tolerance, accepting it when it has between
+/// 3 and 12 vertices equally far from the center and sides of equal length
+fn fit_regular_polygon(points: &[Point], center: Point, tolerance: f64) -> Option<Primitive> {
+    let polygon = Polygon {
+        outline: points.to_vec(),
+        holes: vec![],
+    };
+    let mut vertices =
+        simplify(&polygon, &SimplificationMethods::DouglasPeucker, tolerance).outline;
+    // the simplification always keeps the first point, which may lie in the middle of a side
+    if vertices.len() > 3 {
+        let (a, p, b) = (vertices[vertices.len() - 1], vertices[0], vertices[1]);
+        let cross = (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x);
+        if cross.abs() <= tolerance * (b.x - a.x).hypot(b.y - a.y) {
+            vertices.remove(0);
+        }
+    }
+    let n = vertices.len();
+    if n < 3 || n > 12 {
+        return None;
+    }
+    let radii: Vec<f64> = vertices
+        .iter()
+        .map(|p| (p.x - center.x).hypot(p.y - center.y))
+        .collect();
+    let sides: Vec<f64> = (0..n)
+        .map(|i| {
+            let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+            (b.x - a.x).hypot(b.y - a.y)
+        })
+        .collect();
+    let equal = |values: &[f64], slack: f64| {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().all(|v| (v - mean).abs() <= slack)
+    };
+    if !equal(&radii, tolerance) || !equal(&sides, 2f64 * tolerance) {
+        return None;
+    }
+    return Some(Primitive::RegularPolygon { points: vertices });
+}
+
+/// Renders a primitive as an SVG element, mapped through the given transform
+pub fn render_primitive(primitive: &Primitive, transform: &Transform, fill: &str) -> String {
+    let matrix = format!(
+        "matrix({} {} {} {} {} {})",
+        transform.a, transform.b, transform.c, transform.d, transform.e, transform.f
+    );
+    match primitive {
+        Primitive::Circle { center, radius } => format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" transform=\"{}\" fill=\"{}\"/>",
+            fixed(center.x),
+            fixed(center.y),
+            fixed(*radius),
+            matrix,
+            fill
+        ),
+        Primitive::Ellipse {
+            center,
+            rx,
+            ry,
+            angle,
+        } => format!(
+            "<ellipse rx=\"{}\" ry=\"{}\" transform=\"{} translate({} {}) rotate({})\" fill=\"{}\"/>",
+            fixed(*rx),
+            fixed(*ry),
+            matrix,
+            fixed(center.x),
+            fixed(center.y),
+            fixed(*angle),
+            fill
+        ),
+        Primitive::Rect {
+            center,
+            width,
+            height,
+            angle,
+            radius,
+        } => format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" transform=\"{} translate({} {}) rotate({})\" fill=\"{}\"/>",
+            fixed(-width / 2f64),
+            fixed(-height / 2f64),
+            fixed(*width),
+            fixed(*height),
+            fixed(*radius),
+            matrix,
+            fixed(center.x),
+            fixed(center.y),
+            fixed(*angle),
+            fill
+        ),
+        Primitive::RegularPolygon { points } => {
+            let points: Vec<String> = points
+                .iter()
+                .map(|p| {
+                    let p = transform.apply(*p);
+                    format!("{},{}", fixed(p.x), fixed(p.y))
+                })
+                .collect();
+            format!(
+                "<polygon points=\"{}\" fill=\"{}\"/>",
+                points.join(" "),
+                fill
+            )
+        }
+    }
+}
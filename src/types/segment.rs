@@ -0,0 +1,761 @@
+use super::curve::bezier;
+use super::point::{distance_between, interval, Point};
+use super::transform::Transform;
+use std::f64::consts::PI;
+
+/// Abscissae and weights of 5 point Gauss-Legendre quadrature over -1..1
+const GAUSS: [(f64, f64); 5] = [
+    (0.0, 0.5688888888888889),
+    (-0.5384693101056831, 0.4786286704993665),
+    (0.5384693101056831, 0.4786286704993665),
+    (-0.9061798459386640, 0.2369268850561891),
+    (0.9061798459386640, 0.2369268850561891),
+];
+
+/// A single piece of a traced outline
+#[derive(Copy, Clone)]
+pub enum Segment {
+    Line {
+        from: Point,
+        to: Point,
+    },
+    Cubic {
+        from: Point,
+        c1: Point,
+        c2: Point,
+        to: Point,
+    },
+}
+
+/// A piece of an outline made of lines and quadratic Bezier curves only
+#[derive(Copy, Clone)]
+pub enum QuadraticSegment {
+    Line {
+        from: Point,
+        to: Point,
+    },
+    Quadratic {
+        from: Point,
+        control: Point,
+        to: Point,
+    },
+}
+
+/// A piece of an outline made of lines and circular arcs only, as used by CNC controllers
+#[derive(Copy, Clone)]
+pub enum ArcSegment {
+    Line {
+        from: Point,
+        to: Point,
+    },
+    /// arc around center, running clockwise on screen (positive angles with y pointing down)
+    /// when clockwise is set
+    Arc {
+        from: Point,
+        to: Point,
+        center: Point,
+        radius: f64,
+        clockwise: bool,
+    },
+}
+
+impl Segment {
+    pub fn start(&self) -> Point {
+        match *self {
+            Segment::Line { from, .. } | Segment::Cubic { from, .. } => from,
+        }
+    }
+
+    pub fn end(&self) -> Point {
+        match *self {
+            Segment::Line { to, .. } | Segment::Cubic { to, .. } => to,
+        }
+    }
+
+    /// Returns the segment with every point mapped through the given transform
+    pub fn transform(&self, transform: &Transform) -> Segment {
+        match *self {
+            Segment::Line { from, to } => Segment::Line {
+                from: transform.apply(from),
+                to: transform.apply(to),
+            },
+            Segment::Cubic { from, c1, c2, to } => Segment::Cubic {
+                from: transform.apply(from),
+                c1: transform.apply(c1),
+                c2: transform.apply(c2),
+                to: transform.apply(to),
+            },
+        }
+    }
+
+    /// Returns the same segment running from its end to its start
+    pub fn reverse(&self) -> Segment {
+        match *self {
+            Segment::Line { from, to } => Segment::Line { from: to, to: from },
+            Segment::Cubic { from, c1, c2, to } => Segment::Cubic {
+                from: to,
+                c1: c2,
+                c2: c1,
+                to: from,
+            },
+        }
+    }
+
+    /// return the point at parameter t in [0..1]
+    pub fn point_at(&self, t: f64) -> Point {
+        match *self {
+            Segment::Line { from, to } => interval(t, from, to),
+            Segment::Cubic { from, c1, c2, to } => bezier(t, from, c1, c2, to),
+        }
+    }
+
+    /// return the first derivative at parameter t in [0..1]
+    pub fn derivative_at(&self, t: f64) -> Point {
+        match *self {
+            Segment::Line { from, to } => Point::new(to.x - from.x, to.y - from.y),
+            Segment::Cubic { from, c1, c2, to } => {
+                let s = 1f64 - t;
+                let (a, b, c) = (3f64 * s * s, 6f64 * s * t, 3f64 * t * t);
+                Point::new(
+                    a * (c1.x - from.x) + b * (c2.x - c1.x) + c * (to.x - c2.x),
+                    a * (c1.y - from.y) + b * (c2.y - c1.y) + c * (to.y - c2.y),
+                )
+            }
+        }
+    }
+
+    /// Returns the integral of (x dy - y dx) / 2 along the segment. Summed over a closed
+    /// outline this is its signed area, exactly.
+    pub fn area(&self) -> f64 {
+        match *self {
+            Segment::Line { from, to } => (from.x * to.y - to.x * from.y) / 2f64,
+            Segment::Cubic { from, c1, c2, to } => {
+                let (x0, y0, x1, y1) = (from.x, from.y, c1.x, c1.y);
+                let (x2, y2, x3, y3) = (c2.x, c2.y, to.x, to.y);
+                3f64 * ((y3 - y0) * (x1 + x2) - (x3 - x0) * (y1 + y2) + y1 * (x0 - x2)
+                    - x1 * (y0 - y2)
+                    + y3 * (x2 + x0 / 3f64)
+                    - x3 * (y2 + y0 / 3f64))
+                    / 20f64
+            }
+        }
+    }
+
+    /// Returns the arc length of the part of the segment between parameters t0 and t1
+    pub fn length_between(&self, t0: f64, t1: f64) -> f64 {
+        match *self {
+            Segment::Line { from, to } => distance_between(from, to) * (t1 - t0).abs(),
+            Segment::Cubic { .. } => {
+                // split in pieces so that the quadrature copes with sharp bends
+                let pieces = 8;
+                let step = (t1 - t0) / pieces as f64;
+                let mut length = 0f64;
+                for i in 0..pieces {
+                    let (a, b) = (t0 + step * i as f64, t0 + step * (i + 1) as f64);
+                    for (x, w) in GAUSS.iter() {
+                        let d = self.derivative_at((a + b) / 2f64 + (b - a) / 2f64 * x);
+                        length += w * (b - a).abs() / 2f64 * (d.x * d.x + d.y * d.y).sqrt();
+                    }
+                }
+                length
+            }
+        }
+    }
+
+    /// Returns the arc length of the segment
+    pub fn length(&self) -> f64 {
+        self.length_between(0f64, 1f64)
+    }
+
+    /// Returns parameters in (0..1) where the given coordinate of the segment, 0 for x and 1 for
+    /// y, reaches a local minimum or maximum
+    pub fn extrema(&self, axis: usize) -> Vec<f64> {
+        let (from, c1, c2, to) = match *self {
+            Segment::Line { .. } => return vec![],
+            Segment::Cubic { from, c1, c2, to } => (from, c1, c2, to),
+        };
+        let pick = |p: Point| if axis == 0 { p.x } else { p.y };
+        let (p0, p1, p2, p3) = (pick(from), pick(c1), pick(c2), pick(to));
+        // derivative is 3 (a t^2 + b t + c)
+        let a = -p0 + 3f64 * p1 - 3f64 * p2 + p3;
+        let b = 2f64 * (p0 - 2f64 * p1 + p2);
+        let c = p1 - p0;
+        let mut roots = vec![];
+        if a.abs() < 1e-12 {
+            if b.abs() > 1e-12 {
+                roots.push(-c / b);
+            }
+        } else {
+            let d = b * b - 4f64 * a * c;
+            if d >= 0f64 {
+                let s = d.sqrt();
+                roots.push((-b + s) / (2f64 * a));
+                roots.push((-b - s) / (2f64 * a));
+            }
+        }
+        roots.retain(|t| *t > 0f64 && *t < 1f64);
+        roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        return roots;
+    }
+
+    /// Returns the exact bounding box of the segment as its top left and bottom right corners
+    pub fn bounds(&self) -> (Point, Point) {
+        let (start, end) = (self.start(), self.end());
+        let mut min = Point::new(start.x.min(end.x), start.y.min(end.y));
+        let mut max = Point::new(start.x.max(end.x), start.y.max(end.y));
+        for axis in 0..2 {
+            for t in self.extrema(axis) {
+                let p = self.point_at(t);
+                min = Point::new(min.x.min(p.x), min.y.min(p.y));
+                max = Point::new(max.x.max(p.x), max.y.max(p.y));
+            }
+        }
+        return (min, max);
+    }
+
+    /// Returns the parameter at which the arc length from the start of the segment reaches
+    /// `length`, using Newton's method safeguarded by bisection
+    pub fn parameter_at_length(&self, length: f64) -> f64 {
+        let total = self.length();
+        if total <= 0f64 || length <= 0f64 {
+            return 0f64;
+        }
+        if length >= total {
+            return 1f64;
+        }
+        if let Segment::Line { .. } = *self {
+            return length / total;
+        }
+        let (mut low, mut high) = (0f64, 1f64);
+        let mut t = length / total;
+        for _ in 0..32 {
+            let error = self.length_between(0f64, t) - length;
+            if error.abs() < 1e-9 {
+                break;
+            }
+            if error > 0f64 {
+                high = t;
+            } else {
+                low = t;
+            }
+            let d = self.derivative_at(t);
+            let speed = (d.x * d.x + d.y * d.y).sqrt();
+            let next = if speed > 0f64 {
+                t - error / speed
+            } else {
+                -1f64
+            };
+            t = if next > low && next < high {
+                next
+            } else {
+                (low + high) / 2f64
+            };
+        }
+        return t;
+    }
+
+    /// Returns the parameter of the point of the segment nearest to `p`, and its distance
+    pub fn nearest(&self, p: Point) -> (f64, f64) {
+        let distance = |t: f64| distance_between(self.point_at(t), p);
+        if let Segment::Line { from, to } = *self {
+            let (dx, dy) = (to.x - from.x, to.y - from.y);
+            let length = dx * dx + dy * dy;
+            let t = if length > 0f64 {
+                (((p.x - from.x) * dx + (p.y - from.y) * dy) / length)
+                    .max(0f64)
+                    .min(1f64)
+            } else {
+                0f64
+            };
+            return (t, distance(t));
+        }
+        // coarse sampling, then narrowing the step around the best sample
+        let steps = 16;
+        let mut best = (0f64, distance(0f64));
+        for i in 1..=steps {
+            let t = i as f64 / steps as f64;
+            let d = distance(t);
+            if d < best.1 {
+                best = (t, d);
+            }
+        }
+        let mut step = 1f64 / steps as f64;
+        while step > 1e-9 {
+            step /= 2f64;
+            for t in &[best.0 - step, best.0 + step] {
+                let t = t.max(0f64).min(1f64);
+                let d = distance(t);
+                if d < best.1 {
+                    best = (t, d);
+                }
+            }
+        }
+        return best;
+    }
+
+    /// Counts the crossings of the segment with the ray going from (x, y) in the positive x
+    /// direction. Endpoints lying on the ray count as below it, so that a vertex shared by two
+    /// segments is counted once.
+    pub fn crossings(&self, x: f64, y: f64) -> usize {
+        let (min, max) = self.bounds();
+        if y < min.y || y > max.y || x > max.x {
+            return 0;
+        }
+        // split into pieces monotone in y, each crossing the ray at most once
+        let mut ts = vec![0f64];
+        ts.extend(self.extrema(1));
+        ts.push(1f64);
+        let mut count = 0;
+        for pair in ts.windows(2) {
+            let (mut a, mut b) = (pair[0], pair[1]);
+            let (ya, yb) = (self.point_at(a).y, self.point_at(b).y);
+            if (ya > y) == (yb > y) {
+                continue;
+            }
+            for _ in 0..48 {
+                let mid = (a + b) / 2f64;
+                if (self.point_at(mid).y > y) == (ya > y) {
+                    a = mid;
+                } else {
+                    b = mid;
+                }
+            }
+            if self.point_at((a + b) / 2f64).x > x {
+                count += 1;
+            }
+        }
+        return count;
+    }
+
+    /// Splits the segment at parameter t into two segments, using de Casteljau's algorithm for
+    /// cubic segments
+    pub fn split(&self, t: f64) -> (Segment, Segment) {
+        match *self {
+            Segment::Line { from, to } => {
+                let mid = interval(t, from, to);
+                (
+                    Segment::Line { from, to: mid },
+                    Segment::Line { from: mid, to },
+                )
+            }
+            Segment::Cubic { from, c1, c2, to } => {
+                let (a, b, c) = (
+                    interval(t, from, c1),
+                    interval(t, c1, c2),
+                    interval(t, c2, to),
+                );
+                let (d, e) = (interval(t, a, b), interval(t, b, c));
+                let mid = interval(t, d, e);
+                (
+                    Segment::Cubic {
+                        from,
+                        c1: a,
+                        c2: d,
+                        to: mid,
+                    },
+                    Segment::Cubic {
+                        from: mid,
+                        c1: e,
+                        c2: c,
+                        to,
+                    },
+                )
+            }
+        }
+    }
+
+    /// Returns the largest distance of the control points from the chord, which bounds the
+    /// distance of the segment from the chord
+    pub fn flatness(&self) -> f64 {
+        match *self {
+            Segment::Line { .. } => 0f64,
+            Segment::Cubic { from, c1, c2, to } => {
+                let line = Segment::Line { from, to };
+                line.nearest(c1).1.max(line.nearest(c2).1)
+            }
+        }
+    }
+
+    /// Appends points approximating the segment to `points`, all but its start point, so that
+    /// no part of the segment is farther than `tolerance` from the resulting polyline. Cubic
+    /// segments are subdivided in halves until they are flat enough.
+    pub fn flatten(&self, tolerance: f64, points: &mut Vec<Point>) {
+        self.flatten_to_depth(tolerance, points, 0)
+    }
+
+    fn flatten_to_depth(&self, tolerance: f64, points: &mut Vec<Point>, depth: usize) {
+        // 2^16 pieces is far below any sensible tolerance, and guards against NaN
+        if depth >= 16 || self.flatness() <= tolerance {
+            points.push(self.end());
+            return;
+        }
+        let (first, second) = self.split(0.5);
+        first.flatten_to_depth(tolerance, points, depth + 1);
+        second.flatten_to_depth(tolerance, points, depth + 1);
+    }
+
+    /// Approximates the segment with quadratic Bezier segments no farther than `tolerance` from
+    /// it, splitting cubic segments in halves until a single quadratic is close enough
+    pub fn to_quadratics(&self, tolerance: f64) -> Vec<QuadraticSegment> {
+        let mut quadratics = vec![];
+        self.to_quadratics_to_depth(tolerance, &mut quadratics, 0);
+        return quadratics;
+    }
+
+    fn to_quadratics_to_depth(
+        &self,
+        tolerance: f64,
+        quadratics: &mut Vec<QuadraticSegment>,
+        depth: usize,
+    ) {
+        let (from, c1, c2, to) = match *self {
+            Segment::Line { from, to } => {
+                quadratics.push(QuadraticSegment::Line { from, to });
+                return;
+            }
+            Segment::Cubic { from, c1, c2, to } => (from, c1, c2, to),
+        };
+        // the quadratic sharing the end points and tangents' midpoint, which is off by at most
+        // sqrt(3) / 36 times the length of the cubic's third difference
+        let control = Point::new(
+            (3f64 * (c1.x + c2.x) - from.x - to.x) / 4f64,
+            (3f64 * (c1.y + c2.y) - from.y - to.y) / 4f64,
+        );
+        let difference = Point::new(
+            to.x - 3f64 * c2.x + 3f64 * c1.x - from.x,
+            to.y - 3f64 * c2.y + 3f64 * c1.y - from.y,
+        );
+        let error = 3f64.sqrt() / 36f64 * (difference.x.hypot(difference.y));
+        if depth >= 16 || error <= tolerance {
+            quadratics.push(QuadraticSegment::Quadratic { from, control, to });
+            return;
+        }
+        let (first, second) = self.split(0.5);
+        first.to_quadratics_to_depth(tolerance, quadratics, depth + 1);
+        second.to_quadratics_to_depth(tolerance, quadratics, depth + 1);
+    }
+
+    /// Converts the segment to lines and circular arcs, no farther than `tolerance` from it.
+    ///
+    /// Every cubic is replaced by a biarc, two arcs meeting with the same tangent that keep the
+    /// tangents at the ends of the cubic, and split in halves until the biarc fits.
+    pub fn to_arcs(&self, tolerance: f64) -> Vec<ArcSegment> {
+        let mut arcs = vec![];
+        self.to_arcs_to_depth(tolerance, &mut arcs, 0);
+        return arcs;
+    }
+
+    fn to_arcs_to_depth(&self, tolerance: f64, arcs: &mut Vec<ArcSegment>, depth: usize) {
+        let (from, c1, c2, to) = match *self {
+            Segment::Line { from, to } => {
+                arcs.push(ArcSegment::Line { from, to });
+                return;
+            }
+            Segment::Cubic { from, c1, c2, to } => (from, c1, c2, to),
+        };
+        if self.flatness() <= tolerance {
+            arcs.push(ArcSegment::Line { from, to });
+            return;
+        }
+        // tangents at the ends, falling back to the next control point when one coincides
+        let start = if (c1.x, c1.y) != (from.x, from.y) {
+            c1
+        } else {
+            c2
+        };
+        let end = if (c2.x, c2.y) != (to.x, to.y) { c2 } else { c1 };
+        let tangents = (
+            Point::new(start.x - from.x, start.y - from.y),
+            Point::new(to.x - end.x, to.y - end.y),
+        );
+        if let Some((first, second)) = biarc(from, tangents.0, to, tangents.1) {
+            let fits = (1..16).all(|i| {
+                let p = self.point_at(i as f64 / 16f64);
+                first.distance(p).min(second.distance(p)) <= tolerance
+            }) && self.nearest(first.midpoint()).1 <= tolerance
+                && self.nearest(second.midpoint()).1 <= tolerance;
+            if fits || depth >= 16 {
+                arcs.push(first);
+                arcs.push(second);
+                return;
+            }
+        } else if depth >= 16 {
+            arcs.push(ArcSegment::Line { from, to });
+            return;
+        }
+        let (first, second) = self.split(0.5);
+        first.to_arcs_to_depth(tolerance, arcs, depth + 1);
+        second.to_arcs_to_depth(tolerance, arcs, depth + 1);
+    }
+
+    /// Returns `steps` + 1 points evenly spaced in parameter along the segment
+    pub fn sample(&self, steps: usize) -> Vec<Point> {
+        match *self {
+            Segment::Line { from, to } => vec![from, to],
+            Segment::Cubic { .. } => (0..=steps)
+                .map(|i| self.point_at(i as f64 / steps as f64))
+                .collect(),
+        }
+    }
+}
+
+/// Replaces segments of a closed outline that are straight within `tolerance` with lines, and
+/// merges consecutive lines into one as long as the original segments stay within `tolerance`
+/// of it
+pub fn straighten(segments: &[Segment], tolerance: f64) -> Vec<Segment> {
+    // merged lines, with the vertices each of them replaces and the control points of the
+    // cubics they replace, which bound those cubics
+    let mut lines: Vec<(Segment, Vec<Point>)> = vec![];
+    let merge = |first: &(Segment, Vec<Point>), second: &(Segment, Vec<Point>)| {
+        if let (Segment::Line { from, .. }, Segment::Line { from: middle, to }) =
+            (first.0, second.0)
+        {
+            let line = Segment::Line { from, to };
+            let mut vertices = first.1.clone();
+            vertices.push(middle);
+            vertices.extend(second.1.iter().cloned());
+            if vertices.iter().all(|p| line.nearest(*p).1 <= tolerance) {
+                return Some((line, vertices));
+            }
+        }
+        return None;
+    };
+
+    for segment in segments {
+        let current = match *segment {
+            Segment::Cubic { from, c1, c2, to } if segment.flatness() <= tolerance => {
+                (Segment::Line { from, to }, vec![c1, c2])
+            }
+            _ => (*segment, vec![]),
+        };
+        let merged = match lines.last() {
+            Some(last) => merge(last, &current),
+            None => None,
+        };
+        match merged {
+            Some(line) => *lines.last_mut().unwrap() = line,
+            None => lines.push(current),
+        }
+    }
+    // the outline is closed, so the last line may continue into the first
+    if lines.len() > 2 {
+        if let Some(line) = merge(&lines[lines.len() - 1], &lines[0]) {
+            lines.pop();
+            lines[0] = line;
+        }
+    }
+    return lines.into_iter().map(|(segment, _)| segment).collect();
+}
+
+impl ArcSegment {
+    pub fn start(&self) -> Point {
+        match *self {
+            ArcSegment::Line { from, .. } | ArcSegment::Arc { from, .. } => from,
+        }
+    }
+
+    pub fn end(&self) -> Point {
+        match *self {
+            ArcSegment::Line { to, .. } | ArcSegment::Arc { to, .. } => to,
+        }
+    }
+
+    /// Returns the same segment running the other way
+    pub fn reverse(&self) -> ArcSegment {
+        match *self {
+            ArcSegment::Line { from, to } => ArcSegment::Line { from: to, to: from },
+            ArcSegment::Arc {
+                from,
+                to,
+                center,
+                radius,
+                clockwise,
+            } => ArcSegment::Arc {
+                from: to,
+                to: from,
+                center,
+                radius,
+                clockwise: !clockwise,
+            },
+        }
+    }
+
+    /// return the angle swept from the start of the arc to the direction of p, in [0..2pi)
+    fn sweep_to(&self, p: Point) -> f64 {
+        match *self {
+            ArcSegment::Line { .. } => 0f64,
+            ArcSegment::Arc {
+                from,
+                center,
+                clockwise,
+                ..
+            } => {
+                let start = (from.y - center.y).atan2(from.x - center.x);
+                let angle = (p.y - center.y).atan2(p.x - center.x);
+                let sweep = if clockwise {
+                    angle - start
+                } else {
+                    start - angle
+                };
+                sweep.rem_euclid(2f64 * PI)
+            }
+        }
+    }
+
+    /// Angle the arc spans, in radians
+    pub fn sweep(&self) -> f64 {
+        self.sweep_to(self.end())
+    }
+
+    /// Returns whether the arc spans more than half a circle, the SVG large-arc-flag
+    pub fn large_arc(&self) -> bool {
+        self.sweep() > PI
+    }
+
+    /// return the point halfway along the segment
+    pub fn midpoint(&self) -> Point {
+        match *self {
+            ArcSegment::Line { from, to } => interval(0.5, from, to),
+            ArcSegment::Arc {
+                from,
+                center,
+                clockwise,
+                ..
+            } => {
+                let half = self.sweep() / 2f64;
+                let angle = if clockwise { half } else { -half };
+                let (sin, cos) = angle.sin_cos();
+                let (dx, dy) = (from.x - center.x, from.y - center.y);
+                Point::new(
+                    center.x + dx * cos - dy * sin,
+                    center.y + dx * sin + dy * cos,
+                )
+            }
+        }
+    }
+
+    /// return the distance from p to the nearest point of the segment
+    pub fn distance(&self, p: Point) -> f64 {
+        match *self {
+            ArcSegment::Line { from, to } => Segment::Line { from, to }.nearest(p).1,
+            ArcSegment::Arc {
+                from,
+                to,
+                center,
+                radius,
+                ..
+            } => {
+                if self.sweep_to(p) <= self.sweep() {
+                    (distance_between(p, center) - radius).abs()
+                } else {
+                    distance_between(p, from).min(distance_between(p, to))
+                }
+            }
+        }
+    }
+}
+
+/// Returns the arc leaving `from` along `tangent` and ending at `to`, or a line when `to` lies
+/// straight ahead
+fn arc(from: Point, tangent: Point, to: Point) -> ArcSegment {
+    let chord = Point::new(to.x - from.x, to.y - from.y);
+    let length = tangent.x.hypot(tangent.y);
+    let normal = Point::new(-tangent.y / length, tangent.x / length);
+    let offset = chord.x * normal.x + chord.y * normal.y;
+    let squared = chord.x * chord.x + chord.y * chord.y;
+    if offset.abs() <= 1e-9 * squared.sqrt() {
+        return ArcSegment::Line { from, to };
+    }
+    // signed radius, the center lying along the normal
+    let radius = squared / (2f64 * offset);
+    ArcSegment::Arc {
+        from,
+        to,
+        center: Point::new(from.x + radius * normal.x, from.y + radius * normal.y),
+        radius: radius.abs(),
+        clockwise: offset > 0f64,
+    }
+}
+
+/// Returns the biarc from `from` to `to` with the given end tangents, whose arcs meet halfway
+/// between the ends of two tangent lines of equal length
+fn biarc(from: Point, t0: Point, to: Point, t1: Point) -> Option<(ArcSegment, ArcSegment)> {
+    let (l0, l1) = (t0.x.hypot(t0.y), t1.x.hypot(t1.y));
+    if l0 == 0f64 || l1 == 0f64 {
+        return None;
+    }
+    let (t0, t1) = (
+        Point::new(t0.x / l0, t0.y / l0),
+        Point::new(t1.x / l1, t1.y / l1),
+    );
+    let v = Point::new(to.x - from.x, to.y - from.y);
+    let t = Point::new(t0.x + t1.x, t0.y + t1.y);
+    // |v - d t| = 2 d, solved for the tangent length d
+    let a = t.x * t.x + t.y * t.y - 4f64;
+    let vt = v.x * t.x + v.y * t.y;
+    let vv = v.x * v.x + v.y * v.y;
+    let d = if a.abs() < 1e-12 {
+        vv / (2f64 * vt)
+    } else {
+        (vt - (vt * vt - a * vv).sqrt()) / a
+    };
+    if !(d > 0f64) || !d.is_finite() {
+        return None;
+    }
+    let joint = Point::new(
+        (from.x + d * t0.x + to.x - d * t1.x) / 2f64,
+        (from.y + d * t0.y + to.y - d * t1.y) / 2f64,
+    );
+    let first = arc(from, t0, joint);
+    let second = arc(to, Point::new(-t1.x, -t1.y), joint).reverse();
+    return Some((first, second));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_merged_lines_within_the_tolerance_of_the_curves() {
+        // a cubic bulging 0.75 up, then a line along the chord, of a closed triangle
+        let segments = vec![
+            Segment::Cubic {
+                from: Point::new(0f64, 0f64),
+                c1: Point::new(3f64, -1f64),
+                c2: Point::new(7f64, -1f64),
+                to: Point::new(10f64, 0f64),
+            },
+            Segment::Line {
+                from: Point::new(10f64, 0f64),
+                to: Point::new(20f64, 0.8),
+            },
+            Segment::Line {
+                from: Point::new(20f64, 0.8),
+                to: Point::new(10f64, 20f64),
+            },
+            Segment::Line {
+                from: Point::new(10f64, 20f64),
+                to: Point::new(0f64, 0f64),
+            },
+        ];
+        // the vertex at (10, 0) is within 0.4 of a line from (0, 0) to (20, 0.8), but the cubic
+        // is up to 0.95 away from it, so they stay apart
+        let straightened = straighten(&segments, 1f64);
+        assert_eq!(straightened.len(), 4);
+        match straightened[0] {
+            Segment::Line { from, to } => {
+                assert_eq!((from.x, from.y, to.x, to.y), (0.0, 0.0, 10.0, 0.0))
+            }
+            _ => panic!("the flat cubic is a line"),
+        }
+        // a flatter cubic merges
+        let mut flatter = segments.clone();
+        flatter[0] = Segment::Cubic {
+            from: Point::new(0f64, 0f64),
+            c1: Point::new(3f64, 0.3),
+            c2: Point::new(7f64, 0.5),
+            to: Point::new(10f64, 0f64),
+        };
+        assert_eq!(straighten(&flatter, 1f64).len(), 3);
+    }
+}
@@ -0,0 +1,78 @@
+use types::path::Path;
+use types::point::Point;
+use types::transform::Transform;
+
+/// A traced outline together with the holes directly inside it
+#[derive(Clone)]
+pub struct Shape {
+    pub outline: Path,
+    pub holes: Vec<Path>,
+}
+
+impl Shape {
+    /// Returns the exact bounding box of the outline curve as its top left and bottom right
+    /// corners
+    pub fn bounds(&self) -> (Point, Point) {
+        self.outline.curve.bounds()
+    }
+
+    /// Returns the shape with the outline and every hole mapped through the given transform
+    pub fn transform(&self, transform: &Transform) -> Shape {
+        Shape {
+            outline: self.outline.transform(transform),
+            holes: self
+                .holes
+                .iter()
+                .map(|hole| hole.transform(transform))
+                .collect(),
+        }
+    }
+
+    /// Tests whether a point lies inside the traced curves of the shape using the even-odd rule,
+    /// so that points inside a hole are outside of the shape
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let crossings: usize = self.outline.curve.crossings(x, y)
+            + self
+                .holes
+                .iter()
+                .map(|hole| hole.curve.crossings(x, y))
+                .sum::<usize>();
+        return crossings % 2 == 1;
+    }
+}
+
+/// Groups a path list into shapes, attaching every hole to the smallest outline that contains it.
+/// Holes that are not inside any outline are dropped.
+pub fn group_shapes(paths: Vec<Path>) -> Vec<Shape> {
+    let (outlines, holes): (Vec<Path>, Vec<Path>) =
+        paths.into_iter().partition(|path| path.sign == "+");
+    let mut shapes: Vec<Shape> = outlines
+        .into_iter()
+        .map(|outline| Shape {
+            outline,
+            holes: vec![],
+        })
+        .collect();
+
+    for hole in holes {
+        // center of the first pixel of the hole, which is never on an outline
+        let center = hole.first_pixel_center();
+        let (x, y) = (center.x, center.y);
+        let parent = shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, shape)| shape.outline.contains(x, y))
+            .min_by(|(_, a), (_, b)| {
+                a.outline
+                    .area
+                    .abs()
+                    .partial_cmp(&b.outline.area.abs())
+                    .unwrap()
+            })
+            .map(|(i, _)| i);
+        if let Some(i) = parent {
+            shapes[i].holes.push(hole);
+        }
+    }
+    return shapes;
+}
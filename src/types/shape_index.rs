@@ -0,0 +1,94 @@
+use super::point::Point;
+use super::shape::Shape;
+
+/// A uniform grid over the bounding boxes of shapes, answering which shape lies at a point
+/// without testing every shape
+pub struct ShapeIndex {
+    pub shapes: Vec<Shape>,
+    /// bounding box of every shape
+    bounds: Vec<(Point, Point)>,
+    origin: Point,
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    /// indexes of the shapes whose bounding box overlaps each cell, row by row
+    cells: Vec<Vec<usize>>,
+}
+
+impl ShapeIndex {
+    /// Builds the index, sizing cells so that there are about as many cells as shapes
+    pub fn new(shapes: Vec<Shape>) -> ShapeIndex {
+        let bounds: Vec<(Point, Point)> = shapes.iter().map(Shape::bounds).collect();
+        let mut min = Point::new(f64::INFINITY, f64::INFINITY);
+        let mut max = Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (lo, hi) in &bounds {
+            min = Point::new(min.x.min(lo.x), min.y.min(lo.y));
+            max = Point::new(max.x.max(hi.x), max.y.max(hi.y));
+        }
+        if bounds.is_empty() {
+            min = Point::default();
+            max = Point::default();
+        }
+
+        let (width, height) = ((max.x - min.x).max(1f64), (max.y - min.y).max(1f64));
+        let cell_size = (width * height / shapes.len().max(1) as f64).sqrt();
+        let columns = (width / cell_size).ceil() as usize + 1;
+        let rows = (height / cell_size).ceil() as usize + 1;
+        let mut index = ShapeIndex {
+            shapes: vec![],
+            bounds: vec![],
+            origin: min,
+            cell_size,
+            columns,
+            rows,
+            cells: vec![vec![]; columns * rows],
+        };
+        for (i, (lo, hi)) in bounds.iter().enumerate() {
+            let (x0, y0) = index.cell_of(*lo);
+            let (x1, y1) = index.cell_of(*hi);
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    index.cells[y * columns + x].push(i);
+                }
+            }
+        }
+        index.shapes = shapes;
+        index.bounds = bounds;
+        return index;
+    }
+
+    /// return column and row of the cell holding the given point, clamped to the grid
+    fn cell_of(&self, p: Point) -> (usize, usize) {
+        let x = ((p.x - self.origin.x) / self.cell_size).floor().max(0f64) as usize;
+        let y = ((p.y - self.origin.y) / self.cell_size).floor().max(0f64) as usize;
+        return (x.min(self.columns - 1), y.min(self.rows - 1));
+    }
+
+    /// Returns indexes of all shapes containing the point (x, y)
+    pub fn shapes_at(&self, x: f64, y: f64) -> Vec<usize> {
+        let (column, row) = self.cell_of(Point::new(x, y));
+        self.cells[row * self.columns + column]
+            .iter()
+            .cloned()
+            .filter(|i| {
+                let (lo, hi) = self.bounds[*i];
+                x >= lo.x && x <= hi.x && y >= lo.y && y <= hi.y && self.shapes[*i].contains(x, y)
+            })
+            .collect()
+    }
+
+    /// Returns the innermost shape containing the point (x, y), that is the one with the
+    /// smallest outline when shapes are nested inside holes of other shapes
+    pub fn shape_at(&self, x: f64, y: f64) -> Option<&Shape> {
+        self.shapes_at(x, y)
+            .into_iter()
+            .map(|i| &self.shapes[i])
+            .min_by(|a, b| {
+                a.outline
+                    .area
+                    .abs()
+                    .partial_cmp(&b.outline.area.abs())
+                    .unwrap()
+            })
+    }
+}
@@ -0,0 +1,336 @@
+use super::point::Point;
+use super::polygon::Polygon;
+use constants::SimplificationMethods;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// return (b-a)x(c-a), positive when a, b, c turn counterclockwise in a y-up system
+fn turn(a: Point, b: Point, c: Point) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// Tests whether segments ab and cd intersect or touch
+fn segments_touch(a: Point, b: Point, c: Point, d: Point) -> bool {
+    if a.x.max(b.x) < c.x.min(d.x)
+        || c.x.max(d.x) < a.x.min(b.x)
+        || a.y.max(b.y) < c.y.min(d.y)
+        || c.y.max(d.y) < a.y.min(b.y)
+    {
+        return false;
+    }
+    let (d1, d2) = (turn(a, b, c), turn(a, b, d));
+    let (d3, d4) = (turn(c, d, a), turn(c, d, b));
+    if d1 == 0f64 && d2 == 0f64 {
+        // collinear, the bounding boxes overlap
+        return true;
+    }
+    return d1 * d2 <= 0f64 && d3 * d4 <= 0f64;
+}
+
+/// Even-odd test of a point against a closed polygon
+fn inside(polygon: &[Point], p: Point) -> bool {
+    let mut result = false;
+    let n = polygon.len();
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            result = !result;
+        }
+    }
+    return result;
+}
+
+/// Rings of a polygon being simplified, with the vertices still kept
+struct Rings {
+    points: Vec<Vec<Point>>,
+    keep: Vec<Vec<bool>>,
+}
+
+impl Rings {
+    fn new(polygon: &Polygon) -> Rings {
+        let mut points = vec![polygon.outline.clone()];
+        points.extend(polygon.holes.iter().cloned());
+        let keep = points.iter().map(|ring| vec![true; ring.len()]).collect();
+        Rings { points, keep }
+    }
+
+    fn into_polygon(self) -> Polygon {
+        let mut rings: Vec<Vec<Point>> = self
+            .points
+            .iter()
+            .zip(self.keep.iter())
+            .map(|(ring, keep)| {
+                ring.iter()
+                    .zip(keep.iter())
+                    .filter(|(_, kept)| **kept)
+                    .map(|(p, _)| *p)
+                    .collect()
+            })
+            .collect();
+        let outline = rings.remove(0);
+        Polygon {
+            outline,
+            holes: rings,
+        }
+    }
+
+    /// Returns kept vertices of ring r walking from index i to index j, both included
+    fn span(&self, r: usize, i: usize, j: usize) -> Vec<Point> {
+        let n = self.points[r].len();
+        let mut span = vec![];
+        let mut k = i;
+        loop {
+            if self.keep[r][k % n] {
+                span.push(self.points[r][k % n]);
+            }
+            if k % n == j % n {
+                break;
+            }
+            k += 1;
+        }
+        return span;
+    }
+
+    /// Tests whether the kept vertices strictly between indexes i and j of ring r can be removed
+    /// without the new edge touching any other edge, and without any other vertex ending up on
+    /// the other side of it. Rings also keep at least 3 vertices.
+    fn can_collapse(&self, r: usize, i: usize, j: usize) -> bool {
+        let n = self.points[r].len();
+        let (i, j) = (i % n, j % n);
+        let in_span = |k: usize| (k + n - i) % n <= (j + n - i) % n;
+        let span = self.span(r, i, j);
+        let kept = self.keep[r].iter().filter(|kept| **kept).count();
+        if kept + 2 - span.len() < 3 {
+            return false;
+        }
+        let (a, b) = (self.points[r][i], self.points[r][j]);
+
+        for (s, ring) in self.points.iter().enumerate() {
+            let kept: Vec<usize> = (0..ring.len()).filter(|k| self.keep[s][*k]).collect();
+            for (e, k) in kept.iter().enumerate() {
+                let next = kept[(e + 1) % kept.len()];
+                let own = s == r && in_span(*k);
+                if !own && inside(&span, ring[*k]) {
+                    return false;
+                }
+                // skip edges that get removed, and the ones meeting the new edge at its ends
+                if (own && (in_span(next) || *k == j)) || (s == r && next == i) {
+                    continue;
+                }
+                if segments_touch(a, b, ring[*k], ring[next]) {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    /// Ramer-Douglas-Peucker simplification of ring r, collapsing spans whose vertices are all
+    /// within `tolerance` of the chord when that keeps the topology
+    fn douglas_peucker(&mut self, r: usize, tolerance: f64) {
+        let ring = self.points[r].clone();
+        let n = ring.len();
+        if n <= 3 {
+            return;
+        }
+        let distance = |a: Point, b: Point, p: Point| -> f64 {
+            let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+            if length == 0f64 {
+                ((p.x - a.x).powi(2) + (p.y - a.y).powi(2)).sqrt()
+            } else {
+                turn(a, b, p).abs() / length
+            }
+        };
+        // a closed ring starts out as two spans, between the first vertex and the one farthest
+        // from it
+        let far = (1..n)
+            .map(|k| (k, distance(ring[0], ring[0], ring[k])))
+            .max_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+            .unwrap()
+            .0;
+        let mut spans = vec![(0, far), (far, n)];
+        while let Some((i, j)) = spans.pop() {
+            if j - i < 2 {
+                continue;
+            }
+            let (a, b) = (ring[i], ring[j % n]);
+            let (m, d) = (i + 1..j)
+                .map(|k| (k, distance(a, b, ring[k % n])))
+                .max_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+                .unwrap();
+            if d <= tolerance && self.can_collapse(r, i, j) {
+                for k in i + 1..j {
+                    self.keep[r][k % n] = false;
+                }
+            } else {
+                spans.push((i, m));
+                spans.push((m, j));
+            }
+        }
+    }
+
+    /// Visvalingam-Whyatt simplification of ring r, repeatedly removing the vertex forming the
+    /// smallest triangle with its neighbours while that area is below `area`, skipping vertices
+    /// whose removal would change the topology
+    fn visvalingam_whyatt(&mut self, r: usize, area: f64) {
+        let ring = self.points[r].clone();
+        let n = ring.len();
+        if n <= 3 {
+            return;
+        }
+        let mut prev: Vec<usize> = (0..n).map(|k| (k + n - 1) % n).collect();
+        let mut next: Vec<usize> = (0..n).map(|k| (k + 1) % n).collect();
+        let mut stamp = vec![0usize; n];
+        let triangle = |p: usize, k: usize, q: usize| turn(ring[p], ring[k], ring[q]).abs() / 2f64;
+
+        let mut heap = BinaryHeap::new();
+        for k in 0..n {
+            heap.push(Candidate {
+                area: triangle(prev[k], k, next[k]),
+                index: k,
+                stamp: 0,
+            });
+        }
+        while let Some(candidate) = heap.pop() {
+            let k = candidate.index;
+            if !self.keep[r][k] || candidate.stamp != stamp[k] {
+                continue;
+            }
+            if candidate.area >= area {
+                break;
+            }
+            if !self.can_collapse(r, prev[k], next[k]) {
+                continue;
+            }
+            self.keep[r][k] = false;
+            let (p, q) = (prev[k], next[k]);
+            next[p] = q;
+            prev[q] = p;
+            for neighbour in [p, q].iter() {
+                let neighbour = *neighbour;
+                stamp[neighbour] += 1;
+                heap.push(Candidate {
+                    area: triangle(prev[neighbour], neighbour, next[neighbour]),
+                    index: neighbour,
+                    stamp: stamp[neighbour],
+                });
+            }
+        }
+    }
+}
+
+/// Vertex waiting for removal, ordered so that the heap pops the smallest area first
+struct Candidate {
+    area: f64,
+    index: usize,
+    stamp: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.area == other.area
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other
+            .area
+            .partial_cmp(&self.area)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Simplifies the outline and holes of a polygon with the given method.
+///
+/// Douglas-Peucker keeps every removed vertex within `tolerance` of the simplified ring, while
+/// Visvalingam-Whyatt removes vertices whose triangle with their neighbours is smaller than
+/// `tolerance` squared. Either way a vertex is only removed when the new edge does not touch any
+/// other edge of the polygon and no vertex ends up on its other side, so rings never intersect
+/// and holes stay inside the outline.
+pub fn simplify(polygon: &Polygon, method: &SimplificationMethods, tolerance: f64) -> Polygon {
+    let mut rings = Rings::new(polygon);
+    for r in 0..rings.points.len() {
+        match method {
+            SimplificationMethods::DouglasPeucker => rings.douglas_peucker(r, tolerance),
+            SimplificationMethods::VisvalingamWhyatt => {
+                rings.visvalingam_whyatt(r, tolerance * tolerance)
+            }
+        }
+    }
+    return rings.into_polygon();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(points: &[(f64, f64)]) -> Vec<Point> {
+        points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    fn coordinates(ring: &[Point]) -> Vec<(f64, f64)> {
+        ring.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    #[test]
+    fn removes_vertices_close_to_the_edges() {
+        let polygon = Polygon {
+            outline: ring(&[
+                (0.0, 0.0),
+                (2.0, 0.1),
+                (5.0, -0.1),
+                (10.0, 0.0),
+                (10.0, 10.0),
+                (0.0, 10.0),
+            ]),
+            holes: vec![],
+        };
+        let corners = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        for method in vec![
+            SimplificationMethods::DouglasPeucker,
+            SimplificationMethods::VisvalingamWhyatt,
+        ] {
+            let simplified = simplify(&polygon, &method, 1.0);
+            assert_eq!(coordinates(&simplified.outline), corners);
+        }
+        let kept = simplify(&polygon, &SimplificationMethods::DouglasPeucker, 0.05);
+        assert_eq!(kept.outline.len(), 6);
+    }
+
+    #[test]
+    fn keeps_holes_inside_the_outline() {
+        // a spike within the tolerance of the outline, with a hole inside it
+        let polygon = Polygon {
+            outline: ring(&[
+                (0.0, 0.0),
+                (4.0, 0.0),
+                (5.0, -1.5),
+                (6.0, 0.0),
+                (10.0, 0.0),
+                (10.0, 10.0),
+                (0.0, 10.0),
+            ]),
+            holes: vec![ring(&[(4.8, -0.5), (5.0, -1.0), (5.2, -0.5)])],
+        };
+        for method in vec![
+            SimplificationMethods::DouglasPeucker,
+            SimplificationMethods::VisvalingamWhyatt,
+        ] {
+            let simplified = simplify(&polygon, &method, 2.0);
+            assert!(coordinates(&simplified.outline).contains(&(5.0, -1.5)));
+            assert_eq!(simplified.holes[0].len(), 3);
+            assert!(simplified.holes[0]
+                .iter()
+                .all(|p| inside(&simplified.outline, *p)));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::point::Point;
+
+/// An affine transformation, mapping (x, y) to (a x + c y + e, b x + d y + f) like an SVG
+/// matrix(a b c d e f)
+#[derive(Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Transform {
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Transform {
+        Transform { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(1f64, 0f64, 0f64, 1f64, 0f64, 0f64)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Transform {
+        Transform::new(sx, 0f64, 0f64, sy, 0f64, 0f64)
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Transform {
+        Transform::new(1f64, 0f64, 0f64, 1f64, tx, ty)
+    }
+
+    /// Rotation by the given angle in degrees, clockwise on screen (y pointing down)
+    pub fn rotate(degrees: f64) -> Transform {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Transform::new(cos, sin, -sin, cos, 0f64, 0f64)
+    }
+
+    /// Mirrors left to right
+    pub fn flip_horizontal() -> Transform {
+        Transform::scale(-1f64, 1f64)
+    }
+
+    /// Mirrors top to bottom
+    pub fn flip_vertical() -> Transform {
+        Transform::scale(1f64, -1f64)
+    }
+
+    /// Returns the transform applying this one first and then `next`
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform::new(
+            next.a * self.a + next.c * self.b,
+            next.b * self.a + next.d * self.b,
+            next.a * self.c + next.c * self.d,
+            next.b * self.c + next.d * self.d,
+            next.a * self.e + next.c * self.f + next.e,
+            next.b * self.e + next.d * self.f + next.f,
+        )
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        Point::new(
+            self.a * p.x + self.c * p.y + self.e,
+            self.b * p.x + self.d * p.y + self.f,
+        )
+    }
+
+    /// Returns the bounding box of the given rectangle after transformation, as its top left and
+    /// bottom right corners
+    pub fn apply_to_rect(&self, min: Point, max: Point) -> (Point, Point) {
+        let corners = [
+            self.apply(min),
+            self.apply(Point::new(max.x, min.y)),
+            self.apply(max),
+            self.apply(Point::new(min.x, max.y)),
+        ];
+        let mut lo = corners[0];
+        let mut hi = corners[0];
+        for p in corners.iter() {
+            lo = Point::new(lo.x.min(p.x), lo.y.min(p.y));
+            hi = Point::new(hi.x.max(p.x), hi.y.max(p.y));
+        }
+        return (lo, hi);
+    }
+}
@@ -1,5 +1,7 @@
 use cached::proc_macro::cached;
+use constants::{Channels, ColorMetrics, PageSizes, Units};
 use regex::{Captures, Regex};
+use std::f64::consts::PI;
 
 #[cached]
 pub fn get_attr_regexp(attr_name: &'static str) -> Regex {
@@ -56,3 +58,321 @@ pub fn sign(n: f64) -> f64 {
 pub fn luminance(r: u8, g: u8, b: u8) -> usize {
   return (0.2126 * r as f64 + 0.7153 * g as f64 + 0.0721 * b as f64).round() as usize;
 }
+
+/// Rec. 601 luma of gamma-encoded values
+pub fn luma_601(r: u8, g: u8, b: u8) -> usize {
+  return (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as usize;
+}
+
+/// convert an 8 bit sRGB component to linear light in range of 0..1
+pub fn srgb_to_linear(c: u8) -> f64 {
+  let c = c as f64 / 255f64;
+  if c <= 0.04045 {
+    c / 12.92
+  } else {
+    ((c + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+/// relative luminance (Y) of an sRGB color in range of 0..1
+pub fn relative_luminance(r: u8, g: u8, b: u8) -> f64 {
+  0.2126 * srgb_to_linear(r) + 0.7152 * srgb_to_linear(g) + 0.0722 * srgb_to_linear(b)
+}
+
+/// CIE L* lightness of an sRGB color in range of 0..100
+pub fn lightness(r: u8, g: u8, b: u8) -> f64 {
+  let y = relative_luminance(r, g, b);
+  let f = if y > 216f64 / 24389f64 {
+    y.cbrt()
+  } else {
+    (24389f64 / 27f64 * y + 16f64) / 116f64
+  };
+  return 116f64 * f - 16f64;
+}
+
+/// HSV saturation of an sRGB color in range of 0..1
+pub fn saturation(r: u8, g: u8, b: u8) -> f64 {
+  let max = r.max(g).max(b);
+  let min = r.min(g).min(b);
+  if max == 0 {
+    0f64
+  } else {
+    (max - min) as f64 / max as f64
+  }
+}
+
+/// return the level (0..255) of a RGBA pixel in the given channel
+pub fn channel_value(pixel: &[u8], channel: &Channels) -> u8 {
+  let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+  let value = match channel {
+    Channels::Red => r as f64,
+    Channels::Green => g as f64,
+    Channels::Blue => b as f64,
+    Channels::Alpha => (255 - a) as f64,
+    Channels::Rec601 => luma_601(r, g, b) as f64,
+    Channels::Rec709 => luminance(r, g, b) as f64,
+    Channels::LinearLuminance => relative_luminance(r, g, b) * 255f64,
+    Channels::Lightness => lightness(r, g, b) * 2.55,
+    Channels::Value => r.max(g).max(b) as f64,
+    Channels::Saturation => saturation(r, g, b) * 255f64,
+    Channels::Custom { red, green, blue } => {
+      let total = red + green + blue;
+      if total == 0f64 {
+        0f64
+      } else {
+        (red * r as f64 + green * g as f64 + blue * b as f64) / total
+      }
+    }
+  };
+  return value.round().max(0f64).min(255f64) as u8;
+}
+
+/// parse a hex color of form "#rrggbb" or "#rgb", the hash being optional
+pub fn parse_hex_color(hex: &str) -> Option<[u8; 3]> {
+  let digits = hex.trim().trim_start_matches('#');
+  // slicing below is by byte, so anything but ASCII hex digits is rejected first
+  if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+    return None;
+  }
+  let expanded: String = match digits.len() {
+    3 => digits.chars().flat_map(|c| vec![c, c]).collect(),
+    6 => digits.to_string(),
+    _ => return None,
+  };
+  let mut color = [0u8; 3];
+  for i in 0..3 {
+    color[i] = u8::from_str_radix(&expanded[i * 2..i * 2 + 2], 16).ok()?;
+  }
+  return Some(color);
+}
+
+/// HSV hue of an sRGB color in degrees, or None for shades of gray
+pub fn hue(r: u8, g: u8, b: u8) -> Option<f64> {
+  let (r, g, b) = (r as f64, g as f64, b as f64);
+  let max = r.max(g).max(b);
+  let delta = max - r.min(g).min(b);
+  if delta == 0f64 {
+    return None;
+  }
+  let h = if max == r {
+    ((g - b) / delta) % 6f64
+  } else if max == g {
+    (b - r) / delta + 2f64
+  } else {
+    (r - g) / delta + 4f64
+  };
+  return Some((h * 60f64 + 360f64) % 360f64);
+}
+
+/// convert an sRGB color to CIELAB, using D65 white point
+pub fn rgb_to_lab(r: u8, g: u8, b: u8) -> [f64; 3] {
+  let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+  let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+  let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+  let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / 1.08883;
+  let f = |t: f64| {
+    if t > 216f64 / 24389f64 {
+      t.cbrt()
+    } else {
+      (24389f64 / 27f64 * t + 16f64) / 116f64
+    }
+  };
+  let (fx, fy, fz) = (f(x), f(y), f(z));
+  return [116f64 * fy - 16f64, 500f64 * (fx - fy), 200f64 * (fy - fz)];
+}
+
+/// CIEDE2000 color difference between two CIELAB colors
+pub fn delta_e_2000(lab1: [f64; 3], lab2: [f64; 3]) -> f64 {
+  let [l1, a1, b1] = lab1;
+  let [l2, a2, b2] = lab2;
+  let deg = PI / 180f64;
+  let c_mean = ((a1 * a1 + b1 * b1).sqrt() + (a2 * a2 + b2 * b2).sqrt()) / 2f64;
+  let g = 0.5 * (1f64 - (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt());
+  let (a1, a2) = (a1 * (1f64 + g), a2 * (1f64 + g));
+  let (c1, c2) = ((a1 * a1 + b1 * b1).sqrt(), (a2 * a2 + b2 * b2).sqrt());
+  let angle = |b: f64, a: f64| {
+    if a == 0f64 && b == 0f64 {
+      0f64
+    } else {
+      (b.atan2(a) / deg + 360f64) % 360f64
+    }
+  };
+  let (h1, h2) = (angle(b1, a1), angle(b2, a2));
+
+  let dl = l2 - l1;
+  let dc = c2 - c1;
+  let dh = if c1 * c2 == 0f64 {
+    0f64
+  } else if (h2 - h1).abs() <= 180f64 {
+    h2 - h1
+  } else if h2 <= h1 {
+    h2 - h1 + 360f64
+  } else {
+    h2 - h1 - 360f64
+  };
+  let dh = 2f64 * (c1 * c2).sqrt() * (dh * deg / 2f64).sin();
+
+  let l_mean = (l1 + l2) / 2f64;
+  let c_mean = (c1 + c2) / 2f64;
+  let h_mean = if c1 * c2 == 0f64 {
+    h1 + h2
+  } else if (h1 - h2).abs() <= 180f64 {
+    (h1 + h2) / 2f64
+  } else if h1 + h2 < 360f64 {
+    (h1 + h2 + 360f64) / 2f64
+  } else {
+    (h1 + h2 - 360f64) / 2f64
+  };
+  let t = 1f64 - 0.17 * ((h_mean - 30f64) * deg).cos()
+    + 0.24 * (2f64 * h_mean * deg).cos()
+    + 0.32 * ((3f64 * h_mean + 6f64) * deg).cos()
+    - 0.20 * ((4f64 * h_mean - 63f64) * deg).cos();
+  let sl = 1f64 + 0.015 * (l_mean - 50f64).powi(2) / (20f64 + (l_mean - 50f64).powi(2)).sqrt();
+  let sc = 1f64 + 0.045 * c_mean;
+  let sh = 1f64 + 0.015 * c_mean * t;
+  let rt = -2f64
+    * (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt()
+    * (60f64 * (-((h_mean - 275f64) / 25f64).powi(2)).exp() * deg).sin();
+
+  return ((dl / sl).powi(2) + (dc / sc).powi(2) + (dh / sh).powi(2) + rt * (dc / sc) * (dh / sh))
+    .sqrt();
+}
+
+/// distance between the color of a RGBA pixel and the given color, using the given metric
+pub fn color_distance(pixel: &[u8], color: [u8; 3], metric: &ColorMetrics) -> f64 {
+  let (r, g, b) = (pixel[0], pixel[1], pixel[2]);
+  match metric {
+    ColorMetrics::Rgb => ((r as f64 - color[0] as f64).powi(2)
+      + (g as f64 - color[1] as f64).powi(2)
+      + (b as f64 - color[2] as f64).powi(2))
+    .sqrt(),
+    ColorMetrics::Hue => match (hue(r, g, b), hue(color[0], color[1], color[2])) {
+      (Some(h1), Some(h2)) => {
+        let d = (h1 - h2).abs();
+        d.min(360f64 - d)
+      }
+      (None, None) => 0f64,
+      _ => std::f64::INFINITY,
+    },
+    ColorMetrics::DeltaE2000 => {
+      delta_e_2000(rgb_to_lab(r, g, b), rgb_to_lab(color[0], color[1], color[2]))
+    }
+  }
+}
+
+/// number of the given units in an inch
+pub fn units_per_inch(units: &Units) -> f64 {
+  match units {
+    Units::Px => 96f64,
+    Units::Pt => 72f64,
+    Units::Mm => 25.4,
+    Units::Cm => 2.54,
+    Units::In => 1f64,
+  }
+}
+
+/// suffix of the given units in SVG lengths
+pub fn unit_suffix(units: &Units) -> &'static str {
+  match units {
+    Units::Px => "px",
+    Units::Pt => "pt",
+    Units::Mm => "mm",
+    Units::Cm => "cm",
+    Units::In => "in",
+  }
+}
+
+/// width and height of a page in inches, custom pages being converted from `units_per_inch`
+pub fn page_size_inches(page: &PageSizes, units_per_inch: f64) -> (f64, f64) {
+  match page {
+    PageSizes::A3 => (297f64 / 25.4, 420f64 / 25.4),
+    PageSizes::A4 => (210f64 / 25.4, 297f64 / 25.4),
+    PageSizes::A5 => (148f64 / 25.4, 210f64 / 25.4),
+    PageSizes::Letter => (8.5, 11f64),
+    PageSizes::Legal => (8.5, 14f64),
+    PageSizes::Custom { width, height } => (width / units_per_inch, height / units_per_inch),
+  }
+}
+
+/// Reads the horizontal and vertical resolution in dots per inch stored in the metadata of a PNG
+/// (pHYs chunk) or JPEG (JFIF header) file. Returns None for other formats, and for files that
+/// store no resolution or only an aspect ratio.
+pub fn read_dpi(bytes: &[u8]) -> Option<(f64, f64)> {
+  let be32 = |at: usize| -> Option<u32> {
+    let b = bytes.get(at..at + 4)?;
+    Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+  };
+
+  if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+    let mut pos = 8;
+    while let Some(length) = be32(pos) {
+      let kind = bytes.get(pos + 4..pos + 8)?;
+      if kind == b"pHYs" {
+        let (x, y) = (be32(pos + 8)? as f64, be32(pos + 12)? as f64);
+        // unit 1 is the metre, 0 means the values are just an aspect ratio
+        return match bytes.get(pos + 16)? {
+          1 if x > 0f64 && y > 0f64 => Some((x * 0.0254, y * 0.0254)),
+          _ => None,
+        };
+      }
+      if kind == b"IDAT" || kind == b"IEND" {
+        return None;
+      }
+      pos += 12 + length as usize;
+    }
+    return None;
+  }
+
+  if bytes.starts_with(&[0xFF, 0xD8]) {
+    let app0 = bytes.get(2..18)?;
+    if app0[0] != 0xFF || app0[1] != 0xE0 || &app0[4..9] != b"JFIF\0" {
+      return None;
+    }
+    let x = u16::from_be_bytes([app0[12], app0[13]]) as f64;
+    let y = u16::from_be_bytes([app0[14], app0[15]]) as f64;
+    // unit 1 is dots per inch, 2 dots per centimetre and 0 just an aspect ratio
+    return match app0[11] {
+      1 if x > 0f64 && y > 0f64 => Some((x, y)),
+      2 if x > 0f64 && y > 0f64 => Some((x * 2.54, y * 2.54)),
+      _ => None,
+    };
+  }
+  return None;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_hex_colors() {
+    assert_eq!(parse_hex_color("#E4002B"), Some([0xe4, 0x00, 0x2b]));
+    assert_eq!(parse_hex_color("f0a"), Some([0xff, 0x00, 0xaa]));
+    assert_eq!(parse_hex_color("#ééé"), None);
+    assert_eq!(parse_hex_color("#aéabc"), None);
+    assert_eq!(parse_hex_color("#+1+2+3"), None);
+  }
+
+  #[test]
+  fn matches_published_ciede2000_differences() {
+    // test data of Sharma, Wu and Dalal, "The CIEDE2000 Color-Difference Formula"
+    let pairs = [
+      ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+      ([50.0, 3.1571, -77.2803], [50.0, 0.0, -82.7485], 2.8615),
+      ([50.0, 2.8361, -74.0200], [50.0, 0.0, -82.7485], 3.4412),
+      ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+      ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0009], 7.1792),
+      ([50.0, 2.4900, -0.0010], [50.0, -2.4900, 0.0011], 7.2195),
+      ([50.0, 2.5, 0.0], [50.0, 0.0, -2.5], 4.3065),
+      ([50.0, 2.5, 0.0], [50.0, 3.1736, 0.5854], 1.0000),
+      ([60.2574, -34.0099, 36.2677], [60.4626, -34.1751, 39.4387], 1.2644),
+      ([63.0109, -31.0961, -5.8663], [62.8187, -29.7946, -4.0864], 1.2630),
+      ([90.8027, -2.0831, 1.4410], [91.1528, -1.6435, 0.0447], 1.4441),
+      ([2.0776, 0.0795, -1.1350], [0.9033, -0.0636, -0.5514], 0.9082),
+    ];
+    for (lab1, lab2, expected) in pairs.iter() {
+      assert!((delta_e_2000(*lab1, *lab2) - expected).abs() < 1e-4);
+      assert!((delta_e_2000(*lab2, *lab1) - expected).abs() < 1e-4);
+    }
+  }
+}